@@ -0,0 +1,110 @@
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+// Axis-aligned bounding box
+#[derive(Clone, Copy, Default)]
+pub struct Aabb {
+    minimum: Point3,
+    maximum: Point3,
+}
+
+impl Aabb {
+    pub fn new(a: Point3, b: Point3) -> Aabb {
+        Aabb {
+            minimum: Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())),
+            maximum: Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())),
+        }
+    }
+
+    pub fn min(&self) -> Point3 {
+        self.minimum
+    }
+
+    pub fn max(&self) -> Point3 {
+        self.maximum
+    }
+
+    // Slab test, true if the ray overlaps the box somewhere in [t_min, t_max]
+    pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        let origin = [r.origin().x(), r.origin().y(), r.origin().z()];
+        let direction = [r.direction().x(), r.direction().y(), r.direction().z()];
+        let minimum = [self.minimum.x(), self.minimum.y(), self.minimum.z()];
+        let maximum = [self.maximum.x(), self.maximum.y(), self.maximum.z()];
+
+        for a in 0..3 {
+            let inv_d = 1.0 / direction[a];
+            let mut t0 = (minimum[a] - origin[a]) * inv_d;
+            let mut t1 = (maximum[a] - origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Parameters where the whole ray line enters and leaves the box
+    pub fn interval(&self, r: &Ray) -> Option<(f64, f64)> {
+        let origin = [r.origin().x(), r.origin().y(), r.origin().z()];
+        let direction = [r.direction().x(), r.direction().y(), r.direction().z()];
+        let minimum = [self.minimum.x(), self.minimum.y(), self.minimum.z()];
+        let maximum = [self.maximum.x(), self.maximum.y(), self.maximum.z()];
+
+        let mut t_near = f64::NEG_INFINITY;
+        let mut t_far = f64::INFINITY;
+        for a in 0..3 {
+            let inv_d = 1.0 / direction[a];
+            let mut t0 = (minimum[a] - origin[a]) * inv_d;
+            let mut t1 = (maximum[a] - origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_near = t_near.max(t0);
+            t_far = t_far.min(t1);
+            if t_far <= t_near {
+                return None;
+            }
+        }
+        Some((t_near, t_far))
+    }
+
+    // Grow the box by `delta` on every side, used to give flat shapes some thickness
+    pub fn padded(&self, delta: f64) -> Aabb {
+        let d = Vec3::new(delta, delta, delta);
+        Aabb::new(self.minimum - d, self.maximum + d)
+    }
+}
+
+pub fn surrounding_box(box0: &Aabb, box1: &Aabb) -> Aabb {
+    Aabb::new(
+        Point3::new(
+            box0.minimum.x().min(box1.minimum.x()),
+            box0.minimum.y().min(box1.minimum.y()),
+            box0.minimum.z().min(box1.minimum.z()),
+        ),
+        Point3::new(
+            box0.maximum.x().max(box1.maximum.x()),
+            box0.maximum.y().max(box1.maximum.y()),
+            box0.maximum.z().max(box1.maximum.z()),
+        ),
+    )
+}
+
+// Overlap of two boxes; an empty overlap collapses to a degenerate box
+pub fn overlapping_box(box0: &Aabb, box1: &Aabb) -> Aabb {
+    let minimum = Point3::new(
+        box0.minimum.x().max(box1.minimum.x()),
+        box0.minimum.y().max(box1.minimum.y()),
+        box0.minimum.z().max(box1.minimum.z()),
+    );
+    let maximum = Point3::new(
+        box0.maximum.x().min(box1.maximum.x()),
+        box0.maximum.y().min(box1.maximum.y()),
+        box0.maximum.z().min(box1.maximum.z()),
+    );
+    Aabb::new(minimum, Point3::new(maximum.x().max(minimum.x()), maximum.y().max(minimum.y()), maximum.z().max(minimum.z())))
+}
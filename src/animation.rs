@@ -0,0 +1,268 @@
+use crate::common;
+use crate::vec3::{Point3, Vec3};
+
+// Camera settings at a point in time of an animation
+#[derive(Clone, Copy)]
+pub struct CameraKey {
+    pub time: f64,
+    pub lookfrom: Point3,
+    pub lookat: Point3,
+    pub vup: Vec3,
+    pub vfov: f64, // Vertical field-of-view in degrees
+    pub focus_distance: f64,
+}
+
+#[derive(Clone, Copy)]
+pub enum Interpolation {
+    // Straight lines between keys, with sharp turns at the keys
+    Linear,
+    // Smooth curve passing through every key
+    CatmullRom,
+}
+
+// Camera paths of the command line, see CameraPath::preset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathPreset {
+    // Full turn around the point looked at, at constant height and distance
+    Turntable,
+    // Low flight between the objects, narrowing the field of view
+    Flythrough,
+}
+
+// Keyframed camera path, evaluated at any time between its first and last keys
+pub struct CameraPath {
+    keys: Vec<CameraKey>,
+    interpolation: Interpolation,
+    looping: bool,
+}
+
+impl CameraPath {
+    pub fn new(interpolation: Interpolation) -> CameraPath {
+        CameraPath {
+            keys: Vec::new(),
+            interpolation,
+            looping: false,
+        }
+    }
+
+    // Path played in a loop, whose last key is the same as its first one: the
+    // curve goes smoothly through the seam
+    // Path of `preset` from the key `start` (the still camera) at time 0 to
+    // time 1
+    pub fn preset(preset: PathPreset, interpolation: Interpolation, start: &CameraKey) -> CameraPath {
+        let path = CameraPath::new(interpolation);
+        match preset {
+            PathPreset::Turntable => {
+                let offset = start.lookfrom - start.lookat;
+                let radius = f64::sqrt(offset.x() * offset.x() + offset.z() * offset.z());
+                let angle0 = f64::atan2(offset.z(), offset.x());
+                let keys = 8;
+                let path = (0..=keys).fold(path, |path, k| {
+                    let angle = angle0 + 2.0 * common::PI * (k as f64) / (keys as f64);
+                    let lookfrom = start.lookat + Vec3::new(radius * angle.cos(), offset.y(), radius * angle.sin());
+                    path.key(CameraKey {
+                        time: (k as f64) / (keys as f64),
+                        lookfrom,
+                        ..*start
+                    })
+                });
+                path.looping()
+            }
+            PathPreset::Flythrough => path
+                .key(*start)
+                .key(CameraKey {
+                    time: 0.35,
+                    lookfrom: Point3::new(-8.0, 4.0, -10.0),
+                    lookat: Point3::new(0.0, 1.0, 0.0),
+                    vfov: 50.0,
+                    focus_distance: 13.0,
+                    ..*start
+                })
+                .key(CameraKey {
+                    time: 0.7,
+                    lookfrom: Point3::new(-4.0, 1.5, 4.0),
+                    lookat: Point3::new(2.0, 1.0, 0.0),
+                    vfov: 60.0,
+                    focus_distance: 7.0,
+                    ..*start
+                })
+                .key(CameraKey {
+                    time: 1.0,
+                    lookfrom: Point3::new(6.0, 2.0, 6.0),
+                    lookat: Point3::new(0.0, 1.0, 0.0),
+                    vup: Vec3::new(0.2, 1.0, 0.0),
+                    vfov: 35.0,
+                    focus_distance: 8.5,
+                }),
+        }
+    }
+
+    pub fn looping(mut self) -> CameraPath {
+        self.looping = true;
+        self
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    pub fn key(mut self, key: CameraKey) -> CameraPath {
+        let index = self.keys.partition_point(|k| k.time <= key.time);
+        self.keys.insert(index, key);
+        self
+    }
+
+    // Time of the first and last keys
+    pub fn duration(&self) -> (f64, f64) {
+        match (self.keys.first(), self.keys.last()) {
+            (Some(first), Some(last)) => (first.time, last.time),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    // Times of `frames` images evenly spread over the path. The last image of
+    // a loop comes before its start, so that the sequence plays on without a jerk.
+    pub fn frame_times(&self, frames: usize) -> Vec<f64> {
+        let (start, end) = self.duration();
+        let steps = if self.looping { frames } else { frames.saturating_sub(1).max(1) };
+        (0..frames)
+            .map(|frame| start + (end - start) * (frame as f64) / (steps as f64))
+            .collect()
+    }
+
+    pub fn at(&self, time: f64) -> Option<CameraKey> {
+        let last = self.keys.len().checked_sub(1)?;
+        if time <= self.keys[0].time || last == 0 {
+            return Some(CameraKey { time, ..self.keys[0] });
+        }
+        if time >= self.keys[last].time {
+            return Some(CameraKey { time, ..self.keys[last] });
+        }
+
+        // Segment from key i to key i + 1, with its neighbours for the spline,
+        // which are across the seam at the ends of a loop
+        let i = self.keys.partition_point(|k| k.time <= time) - 1;
+        let k0 = match i {
+            0 if self.looping => &self.keys[last - 1],
+            _ => &self.keys[i.saturating_sub(1)],
+        };
+        let k1 = &self.keys[i];
+        let k2 = &self.keys[i + 1];
+        let k3 = match i + 2 {
+            j if j > last && self.looping => &self.keys[1],
+            j => &self.keys[j.min(last)],
+        };
+        let s = (time - k1.time) / (k2.time - k1.time);
+
+        let point = |f: fn(&CameraKey) -> Vec3| interpolate(self.interpolation, [f(k0), f(k1), f(k2), f(k3)], s);
+        let scalar = |f: fn(&CameraKey) -> f64| {
+            let points = [k0, k1, k2, k3].map(|k| Vec3::new(f(k), 0.0, 0.0));
+            interpolate(self.interpolation, points, s).x()
+        };
+
+        Some(CameraKey {
+            time,
+            lookfrom: point(|k| k.lookfrom),
+            lookat: point(|k| k.lookat),
+            vup: point(|k| k.vup),
+            vfov: scalar(|k| k.vfov),
+            focus_distance: scalar(|k| k.focus_distance),
+        })
+    }
+}
+
+// Value between p[1] (s = 0) and p[2] (s = 1), p[0] and p[3] being the neighbouring keys
+fn interpolate(interpolation: Interpolation, p: [Vec3; 4], s: f64) -> Vec3 {
+    match interpolation {
+        Interpolation::Linear => p[1] + s * (p[2] - p[1]),
+        Interpolation::CatmullRom => catmull_rom(p[0], p[1], p[2], p[3], s),
+    }
+}
+
+// Uniform Catmull-Rom spline
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, s: f64) -> Vec3 {
+    let s2 = s * s;
+    let s3 = s2 * s;
+    0.5 * ((2.0 * p1)
+        + s * (p2 - p0)
+        + s2 * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3)
+        + s3 * (3.0 * p1 - p0 - 3.0 * p2 + p3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_close, assert_vec_close};
+
+    fn key(time: f64, x: f64, z: f64) -> CameraKey {
+        CameraKey {
+            time,
+            lookfrom: Point3::new(x, 1.0, z),
+            lookat: Point3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 40.0 + 20.0 * time,
+            focus_distance: 10.0,
+        }
+    }
+
+    // Square around the origin, back to its first corner
+    fn square(interpolation: Interpolation) -> CameraPath {
+        [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0), (1.0, 0.0)]
+            .iter()
+            .enumerate()
+            .fold(CameraPath::new(interpolation), |path, (i, &(x, z))| path.key(key(i as f64 / 4.0, x, z)))
+    }
+
+    #[test]
+    fn linear_interpolation_between_keys() {
+        let path = square(Interpolation::Linear);
+        assert_eq!(path.duration(), (0.0, 1.0));
+        let middle = path.at(0.125).unwrap();
+        assert_vec_close(middle.lookfrom, Point3::new(0.5, 1.0, 0.5));
+        assert_close(middle.vfov, 42.5);
+        assert_vec_close(path.at(2.0).unwrap().lookfrom, Point3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn splines_pass_through_the_keys() {
+        let path = square(Interpolation::CatmullRom);
+        assert_vec_close(path.at(0.5).unwrap().lookfrom, Point3::new(-1.0, 1.0, 0.0));
+        assert_vec_close(path.looping().at(0.25).unwrap().lookfrom, Point3::new(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn loop_is_smooth_through_the_seam() {
+        let path = square(Interpolation::CatmullRom).looping();
+        let step = 1.0e-4;
+        let after_start = path.at(step).unwrap().lookfrom - path.at(0.0).unwrap().lookfrom;
+        let before_end = path.at(1.0).unwrap().lookfrom - path.at(1.0 - step).unwrap().lookfrom;
+        assert!((after_start - before_end).length() < 1.0e-6, "({}) then ({})", before_end, after_start);
+
+        // Without looping the curve leaves and reaches the seam in other directions
+        let path = square(Interpolation::CatmullRom);
+        let after_start = path.at(step).unwrap().lookfrom - path.at(0.0).unwrap().lookfrom;
+        let before_end = path.at(1.0).unwrap().lookfrom - path.at(1.0 - step).unwrap().lookfrom;
+        assert!((after_start - before_end).length() > 1.0e-5);
+    }
+
+    #[test]
+    fn frames_of_a_loop_stop_before_the_seam() {
+        assert_eq!(square(Interpolation::Linear).frame_times(5), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(square(Interpolation::Linear).looping().frame_times(4), [0.0, 0.25, 0.5, 0.75]);
+        assert_eq!(square(Interpolation::Linear).frame_times(1), [0.0]);
+    }
+
+    #[test]
+    fn turntable_keys_keep_their_height_and_distance() {
+        let start = key(0.0, 3.0, 4.0);
+        let path = CameraPath::preset(PathPreset::Turntable, Interpolation::CatmullRom, &start);
+        assert!(path.is_looping());
+        assert_eq!(path.duration(), (0.0, 1.0));
+        for time in [0.0, 0.125, 0.5, 0.875, 1.0] {
+            let key = path.at(time).unwrap();
+            assert_close(key.lookfrom.y(), 1.0);
+            assert_close((key.lookfrom - key.lookat).length(), (start.lookfrom - start.lookat).length());
+        }
+        assert_vec_close(path.at(0.5).unwrap().lookfrom, Point3::new(-3.0, 1.0, -4.0));
+    }
+}
@@ -0,0 +1,62 @@
+use crate::common;
+use crate::error::Result;
+use crate::texture::{ImageTexture, Texture};
+use crate::vec3::{self, Point3, Vec3};
+
+// Shape of the lens opening, which out of focus highlights (bokeh) take
+pub enum Aperture {
+    Circle,
+    // Regular polygon made by `blades` diaphragm blades, turned by `rotation` radians
+    Polygon { blades: usize, rotation: f64 },
+    // Opening drawn by the bright parts of an image spanning the lens square
+    Mask(ImageTexture),
+}
+
+impl Aperture {
+    pub fn polygon(blades: usize, rotation: f64) -> Aperture {
+        Aperture::Polygon {
+            blades: blades.max(3),
+            rotation: common::degrees_to_radians(rotation),
+        }
+    }
+
+    pub fn mask(path: &str) -> Result<Aperture> {
+        Ok(Aperture::Mask(ImageTexture::load(path)?))
+    }
+
+    // Uniform random point of the opening, in lens coordinates within [-1, 1] (z = 0)
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            Aperture::Circle => vec3::random_in_unit_disk(),
+            Aperture::Polygon { blades, rotation } => {
+                // Pick one of the triangles fanning from the center, then a point in it
+                let n = *blades as f64;
+                let i = (common::random_double() * n).floor();
+                let corner = |k: f64| {
+                    let angle = rotation + 2.0 * common::PI * k / n;
+                    Vec3::new(angle.cos(), angle.sin(), 0.0)
+                };
+                let mut a = common::random_double();
+                let mut b = common::random_double();
+                if a + b > 1.0 {
+                    a = 1.0 - a;
+                    b = 1.0 - b;
+                }
+                a * corner(i) + b * corner(i + 1.0)
+            }
+            Aperture::Mask(image) => {
+                // Rejection sampling with the mask luminance as acceptance probability
+                for _ in 0..256 {
+                    let u = common::random_double();
+                    let v = common::random_double();
+                    let c = image.value(u, v, &Point3::default());
+                    let luminance = 0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z();
+                    if common::random_double() < luminance {
+                        return Vec3::new(2.0 * u - 1.0, 2.0 * v - 1.0, 0.0);
+                    }
+                }
+                Vec3::default()
+            }
+        }
+    }
+}
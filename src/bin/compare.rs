@@ -0,0 +1,77 @@
+use std::env;
+use std::process;
+
+use rt::metrics;
+use rt::Image;
+
+const USAGE: &str = "Usage: cargo run --bin compare <image> <reference> [--heatmap <ppm|png>] [--max-error <value>]";
+
+// Options qui suivent les deux images
+struct Options {
+    heatmap: Option<String>,
+    max_error: Option<f64>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        heatmap: None,
+        max_error: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        let value = args.next().ok_or_else(|| format!("Missing value for option '{}'", option))?;
+        let invalid = || format!("Invalid value '{}' for option '{}'", value, option);
+        match option.as_str() {
+            "--heatmap" => options.heatmap = Some(value.clone()),
+            "--max-error" => options.max_error = Some(value.parse().ok().filter(|x| *x > 0.0).ok_or_else(invalid)?),
+            _ => return Err(format!("Unknown option '{}'", option)),
+        }
+    }
+    Ok(options)
+}
+
+// Comparaison d'un rendu à une image de référence, pour évaluer une
+// modification de l'échantillonnage ou de l'intégrateur
+fn compare(image_name: &str, reference_name: &str, options: &Options) -> rt::Result<()> {
+    let image = Image::load(image_name)?;
+    let reference = Image::load(reference_name)?;
+    let comparison = metrics::compare(&image, &reference)?;
+    println!("RMSE:   {:.6}", comparison.rmse);
+    println!("relMSE: {:.6}", comparison.rel_mse);
+    println!("PSNR:   {:.2} dB", comparison.psnr);
+    println!("SSIM:   {:.4}", comparison.ssim);
+
+    // Carte des erreurs en fausses couleurs, au format de son extension
+    if let Some(heatmap_name) = &options.heatmap {
+        let heatmap = metrics::error_heatmap(&image, &reference, options.max_error)?;
+        if heatmap_name.to_lowercase().ends_with(".png") {
+            heatmap.write_png(heatmap_name)?;
+        } else {
+            heatmap.write_ppm(heatmap_name)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let options = match parse_options(&args[3..]) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = compare(&args[1], &args[2], &options) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
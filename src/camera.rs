@@ -1,61 +1,562 @@
+use crate::aabb::Aabb;
+use crate::animation::CameraKey;
+use crate::aperture::Aperture;
+use crate::color::Color;
 use crate::common;
+use crate::error::Result;
 use crate::ray::Ray;
 use crate::vec3::{self, Point3, Vec3};
- 
-pub struct Camera {
+
+// A camera turns image coordinates (s, t) in [0, 1], from the bottom left
+// corner, into a primary ray. Projections that do not cover the whole image
+// (circular fisheye) return None outside of their image area.
+pub trait Camera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray>;
+
+    // Ray along with the weight of its radiance in each color channel. Lenses
+    // focusing each wavelength differently trace the channels separately.
+    fn get_weighted_ray(&self, s: f64, t: f64) -> Option<(Ray, Color)> {
+        self.get_ray(s, t).map(|r| (r, Color::new(1.0, 1.0, 1.0)))
+    }
+}
+
+// Position, orientation and shutter interval shared by every projection
+#[derive(Clone, Copy)]
+pub struct View {
     origin: Point3,
+    u: Vec3, // Right
+    v: Vec3, // Up
+    w: Vec3, // Backwards, the camera looks along -w
+    time0: f64, // Shutter open/close times
+    time1: f64,
+}
+
+impl View {
+    pub fn new(lookfrom: Point3, lookat: Point3, vup: Vec3) -> View {
+        let w = vec3::unit_vector(lookfrom - lookat);
+        let u = vec3::unit_vector(vec3::cross(vup, w));
+        let v = vec3::cross(w, u);
+        View {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            time0: 0.0,
+            time1: 0.0,
+        }
+    }
+
+    // Keep the shutter open from `time0` to `time1`; rays get a random time in between
+    pub fn with_shutter(mut self, time0: f64, time1: f64) -> View {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+
+    // Direction in world space from camera space coordinates (right, up, forward)
+    fn direction(&self, right: f64, up: f64, forward: f64) -> Vec3 {
+        right * self.u + up * self.v - forward * self.w
+    }
+
+    fn ray(&self, origin: Point3, direction: Vec3) -> Ray {
+        Ray::new(origin, direction, common::random_double_range(self.time0, self.time1))
+    }
+}
+
+// Camera position and target from which a perspective camera looking along
+// `direction` sees the whole box, leaving `margin` (a fraction of the image
+// size) free on the tightest side. Returns (lookfrom, lookat).
+pub fn frame_box(
+    bounds: &Aabb,
+    direction: Vec3,
+    vup: Vec3,
+    vfov: f64,
+    aspect_ratio: f64,
+    margin: f64,
+) -> (Point3, Point3) {
+    let lookat = 0.5 * (bounds.min() + bounds.max());
+    let forward = vec3::unit_vector(direction);
+    let view = View::new(lookat - forward, lookat, vup);
+
+    // Half extent of the usable part of the image at unit distance
+    let tan_y = f64::tan(common::degrees_to_radians(vfov) / 2.0) / (1.0 + 2.0 * margin);
+    let tan_x = aspect_ratio * tan_y;
+
+    // Back off until every corner is inside the field of view: a corner at
+    // (x, y) on the image plane and z along the view needs a distance d with
+    // |x| <= (d + z) * tan_x, and the same vertically
+    let mut distance: f64 = 0.0;
+    for i in 0..8 {
+        let corner = Point3::new(
+            if i & 1 == 0 { bounds.min().x() } else { bounds.max().x() },
+            if i & 2 == 0 { bounds.min().y() } else { bounds.max().y() },
+            if i & 4 == 0 { bounds.min().z() } else { bounds.max().z() },
+        ) - lookat;
+        let x = vec3::dot(corner, view.u).abs();
+        let y = vec3::dot(corner, view.v).abs();
+        let z = vec3::dot(corner, forward);
+        distance = distance.max(x / tan_x - z).max(y / tan_y - z);
+    }
+
+    (lookat - distance * forward, lookat)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+impl Eye {
+    // Side of the eye along the camera right vector
+    fn side(&self) -> f64 {
+        match self {
+            Eye::Left => -1.0,
+            Eye::Right => 1.0,
+        }
+    }
+}
+
+// Thin lens perspective projection
+pub struct PerspectiveCamera {
+    view: View,
     lower_left_corner: Point3,
     horizontal: Vec3,
     vertical: Vec3,
-    u: Vec3,
-    v: Vec3,
+    aspect_ratio: f64,
     lens_radius: f64,
+    aperture: Aperture,
+    cat_eye: f64,
+    chromatic_aberration: f64,
 }
- 
-impl Camera {
+
+impl PerspectiveCamera {
     pub fn new(
-        lookfrom: Point3,
-        lookat: Point3,
-        vup: Vec3,
+        view: View,
         vfov: f64, // Vertical field-of-view in degrees
         aspect_ratio: f64,
         aperture: f64,
         focus_dist: f64,
-    ) -> Camera {
+    ) -> PerspectiveCamera {
         let theta = common::degrees_to_radians(vfov);
         let h = f64::tan(theta / 2.0);
         let viewport_height = 2.0 * h;
         let viewport_width = aspect_ratio * viewport_height;
- 
-        let w = vec3::unit_vector(lookfrom - lookat);
-        let u = vec3::unit_vector(vec3::cross(vup, w));
-        let v = vec3::cross(w, u);
- 
-        let origin = lookfrom;
-        let horizontal = focus_dist * viewport_width * u;
-        let vertical = focus_dist * viewport_height * v;
-        let lower_left_corner = origin - horizontal / 2.0 - vertical / 2.0 - focus_dist * w;
- 
+
+        let horizontal = focus_dist * viewport_width * view.u;
+        let vertical = focus_dist * viewport_height * view.v;
+        let lower_left_corner = view.origin - horizontal / 2.0 - vertical / 2.0 - focus_dist * view.w;
+
         let lens_radius = aperture / 2.0;
- 
-        Camera {
-            origin,
+
+        PerspectiveCamera {
+            view,
             lower_left_corner,
             horizontal,
             vertical,
-            u,
-            v,
+            aspect_ratio,
             lens_radius,
+            aperture: Aperture::Circle,
+            cat_eye: 0.0,
+            chromatic_aberration: 0.0,
+        }
+    }
+
+    pub fn with_aperture(mut self, aperture: Aperture) -> PerspectiveCamera {
+        self.aperture = aperture;
+        self
+    }
+
+    // Mechanical vignetting: away from the image center the lens barrel hides
+    // part of the opening, which turns the bokeh into cat's eyes and darkens the
+    // corners. `amount` is the offset of the barrel at the corners, in aperture radii.
+    pub fn with_cat_eye(mut self, amount: f64) -> PerspectiveCamera {
+        self.cat_eye = amount;
+        self
+    }
+
+    // Longitudinal chromatic aberration: red focuses `amount` times the focus
+    // distance further and blue as much closer, which fringes the defocused parts
+    pub fn with_chromatic_aberration(mut self, amount: f64) -> PerspectiveCamera {
+        self.chromatic_aberration = amount;
+        self
+    }
+
+    // Ray through a random point of the lens, focused at `focus_scale` times the focus distance
+    fn lens_ray(&self, s: f64, t: f64, focus_scale: f64) -> Option<Ray> {
+        let lens = self.aperture.sample();
+        if self.cat_eye > 0.0 {
+            let corner = f64::sqrt(self.aspect_ratio * self.aspect_ratio + 1.0);
+            let barrel = (self.cat_eye / corner) * Vec3::new((2.0 * s - 1.0) * self.aspect_ratio, 2.0 * t - 1.0, 0.0);
+            if (lens - barrel).length_squared() > 1.0 {
+                return None;
+            }
+        }
+
+        let rd = self.lens_radius * lens;
+        let origin = self.view.origin + self.view.u * rd.x() + self.view.v * rd.y();
+        let focus_point = self.lower_left_corner + s * self.horizontal + t * self.vertical - self.view.origin;
+        Some(self.view.ray(origin, self.view.origin + focus_scale * focus_point - origin))
+    }
+
+    // Pinhole camera of one eye of a stereo pair. Both eyes share the same
+    // window at the `convergence` distance (off-axis projection), so objects at
+    // that distance have no parallax and appear on the screen plane.
+    pub fn for_eye(
+        view: View,
+        vfov: f64,
+        aspect_ratio: f64,
+        eye: Eye,
+        ipd: f64,
+        convergence: f64,
+    ) -> PerspectiveCamera {
+        let offset = (eye.side() * ipd / 2.0) * view.u;
+        let eye_view = View {
+            origin: view.origin + offset,
+            ..view
+        };
+        let mut camera = PerspectiveCamera::new(eye_view, vfov, aspect_ratio, 0.0, convergence);
+        camera.lower_left_corner = camera.lower_left_corner - offset;
+        camera
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        self.lens_ray(s, t, 1.0)
+    }
+
+    fn get_weighted_ray(&self, s: f64, t: f64) -> Option<(Ray, Color)> {
+        if self.chromatic_aberration == 0.0 {
+            return self.get_ray(s, t).map(|r| (r, Color::new(1.0, 1.0, 1.0)));
+        }
+
+        // Trace a single channel, weighted by three as it is picked a third of the time
+        let channel = ((common::random_double() * 3.0) as usize).min(2);
+        let (focus_scale, weight) = match channel {
+            0 => (1.0 + self.chromatic_aberration, Color::new(3.0, 0.0, 0.0)),
+            1 => (1.0, Color::new(0.0, 3.0, 0.0)),
+            _ => (1.0 - self.chromatic_aberration, Color::new(0.0, 0.0, 3.0)),
+        };
+        self.lens_ray(s, t, focus_scale).map(|r| (r, weight))
+    }
+}
+
+// Parallel rays through a rectangle of `view_height` world units, for plans and elevations
+pub struct OrthographicCamera {
+    view: View,
+    view_width: f64,
+    view_height: f64,
+}
+
+impl OrthographicCamera {
+    pub fn new(view: View, view_height: f64, aspect_ratio: f64) -> OrthographicCamera {
+        OrthographicCamera {
+            view,
+            view_width: aspect_ratio * view_height,
+            view_height,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let origin = self.view.origin
+            + ((s - 0.5) * self.view_width) * self.view.u
+            + ((t - 0.5) * self.view_height) * self.view.v;
+        Some(self.view.ray(origin, -self.view.w))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FisheyeMapping {
+    // Distance from the image center proportional to the angle from the axis
+    Equidistant,
+    // Equal solid angles cover equal image areas
+    Equisolid,
+}
+
+// Circular fisheye: the image circle spans the image height and covers `fov` degrees
+pub struct FisheyeCamera {
+    view: View,
+    half_fov: f64,
+    aspect_ratio: f64,
+    mapping: FisheyeMapping,
+}
+
+impl FisheyeCamera {
+    pub fn new(view: View, fov: f64, aspect_ratio: f64, mapping: FisheyeMapping) -> FisheyeCamera {
+        FisheyeCamera {
+            view,
+            half_fov: common::degrees_to_radians(fov) / 2.0,
+            aspect_ratio,
+            mapping,
         }
     }
- 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * vec3::random_in_unit_disk();
-        let offset = self.u * rd.x() + self.v * rd.y();
- 
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-        )
+}
+
+impl Camera for FisheyeCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        // Coordinates where the image circle has a radius of one
+        let x = (2.0 * s - 1.0) * self.aspect_ratio;
+        let y = 2.0 * t - 1.0;
+        let radius = f64::sqrt(x * x + y * y);
+        if radius > 1.0 {
+            return None;
+        }
+
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => radius * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * f64::asin(radius * f64::sin(self.half_fov / 2.0)),
+        };
+        let phi = f64::atan2(y, x);
+        let direction = self.view.direction(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        );
+        Some(self.view.ray(self.view.origin, direction))
+    }
+}
+
+// Full 360° by 180° panorama, longitude along s and latitude along t, looking
+// at the center of the image. Meant for a 2:1 image.
+pub struct EquirectangularCamera {
+    view: View,
+}
+
+impl EquirectangularCamera {
+    pub fn new(view: View) -> EquirectangularCamera {
+        EquirectangularCamera { view }
     }
-}
\ No newline at end of file
+}
+
+impl Camera for EquirectangularCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let longitude = (s - 0.5) * 2.0 * common::PI;
+        let latitude = (t - 0.5) * common::PI;
+        let direction = self.view.direction(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            latitude.cos() * longitude.cos(),
+        );
+        Some(self.view.ray(self.view.origin, direction))
+    }
+}
+
+// Panorama on a cylinder around the up axis: angular horizontally over `hfov`
+// degrees, perspective vertically over `vfov` degrees
+pub struct CylindricalCamera {
+    view: View,
+    hfov: f64,
+    half_height: f64,
+}
+
+impl CylindricalCamera {
+    pub fn new(view: View, hfov: f64, vfov: f64) -> CylindricalCamera {
+        CylindricalCamera {
+            view,
+            hfov: common::degrees_to_radians(hfov),
+            half_height: f64::tan(common::degrees_to_radians(vfov) / 2.0),
+        }
+    }
+}
+
+impl Camera for CylindricalCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let angle = (s - 0.5) * self.hfov;
+        let height = (2.0 * t - 1.0) * self.half_height;
+        let direction = self.view.direction(angle.sin(), height, angle.cos());
+        Some(self.view.ray(self.view.origin, direction))
+    }
+}
+
+// Stereo pair rendered side by side, left eye on the left half of the image
+pub struct StereoCamera {
+    left: PerspectiveCamera,
+    right: PerspectiveCamera,
+}
+
+impl StereoCamera {
+    pub fn new(view: View, vfov: f64, eye_aspect_ratio: f64, ipd: f64, convergence: f64) -> StereoCamera {
+        StereoCamera {
+            left: PerspectiveCamera::for_eye(view, vfov, eye_aspect_ratio, Eye::Left, ipd, convergence),
+            right: PerspectiveCamera::for_eye(view, vfov, eye_aspect_ratio, Eye::Right, ipd, convergence),
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        if s < 0.5 {
+            self.left.get_ray(2.0 * s, t)
+        } else {
+            self.right.get_ray(2.0 * s - 1.0, t)
+        }
+    }
+}
+
+// Omni-directional stereo panorama: two equirectangular images stacked over
+// and under, left eye on top. Every ray starts on the circle of diameter `ipd`
+// traced by the eyes when the head turns, tangent to it, which gives correct
+// stereo in every horizontal direction. The eyes merge towards the poles to
+// avoid the swirl artifacts of a constant separation looking straight up or down.
+pub struct OdsCamera {
+    view: View,
+    ipd: f64,
+}
+
+impl OdsCamera {
+    pub fn new(view: View, ipd: f64) -> OdsCamera {
+        OdsCamera { view, ipd }
+    }
+}
+
+impl Camera for OdsCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let (eye, t) = if t >= 0.5 {
+            (Eye::Left, 2.0 * t - 1.0)
+        } else {
+            (Eye::Right, 2.0 * t)
+        };
+
+        let longitude = (s - 0.5) * 2.0 * common::PI;
+        let latitude = (t - 0.5) * common::PI;
+        let direction = self.view.direction(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            latitude.cos() * longitude.cos(),
+        );
+
+        // Right vector of a head turned towards the longitude
+        let right = self.view.direction(longitude.cos(), 0.0, -longitude.sin());
+        let radius = eye.side() * (self.ipd / 2.0) * latitude.cos();
+        Some(self.view.ray(self.view.origin + radius * right, direction))
+    }
+}
+
+// Projection of the camera built by CameraSettings
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+    Fisheye(FisheyeMapping),
+    Equirectangular,
+    Cylindrical,
+    // Perspective projection of one eye of a stereo pair
+    Eye(Eye),
+    // Both eyes side by side
+    Stereo,
+    // Two 2:1 panoramas on top of each other
+    Ods,
+}
+
+// Vertical field of view of the perspective projections by default, in degrees
+pub const DEFAULT_FOV: f64 = 40.0;
+
+// Projection and lens of a camera placed by a CameraKey. The options left
+// to None take the value of the key or of the projection.
+#[derive(Clone, Debug)]
+pub struct CameraSettings {
+    pub projection: Projection,
+    // Vertical field of view, of the whole image circle for the fisheyes
+    pub fov: Option<f64>,
+    // Horizontal field of view of the cylindrical panorama
+    pub hfov: Option<f64>,
+    // Height of the orthographic view
+    pub ortho_height: f64,
+    // Distance between the eyes of the stereo projections and the distance at
+    // which their images converge, the focus distance of the key by default
+    pub ipd: f64,
+    pub convergence: Option<f64>,
+    // Lens of the perspective projection: aperture diameter and focus
+    // distance, shape of the aperture (mask, diaphragm blades or disk) and
+    // its aberrations
+    pub aperture: f64,
+    pub focus_distance: Option<f64>,
+    pub blades: Option<usize>,
+    pub blade_rotation: f64,
+    pub aperture_mask: Option<String>,
+    pub cat_eye: f64,
+    pub chromatic_aberration: f64,
+}
+
+impl Default for CameraSettings {
+    // Pinhole perspective camera; the eyes are those of an adult, the scene
+    // being supposed in meters
+    fn default() -> CameraSettings {
+        CameraSettings {
+            projection: Projection::Perspective,
+            fov: None,
+            hfov: None,
+            ortho_height: 12.0,
+            ipd: 0.064,
+            convergence: None,
+            aperture: 0.0,
+            focus_distance: None,
+            blades: None,
+            blade_rotation: 0.0,
+            aperture_mask: None,
+            cat_eye: 0.0,
+            chromatic_aberration: 0.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    // Field of view of the key, which is that of the perspective projections:
+    // `fov` does not change it for the projections using it otherwise
+    pub fn perspective_fov(&self) -> f64 {
+        match self.projection {
+            Projection::Fisheye(_) | Projection::Cylindrical => DEFAULT_FOV,
+            _ => self.fov.unwrap_or(DEFAULT_FOV),
+        }
+    }
+
+    // Camera placed by `key`, with the aspect ratio of the image it needs when
+    // the image of a single view has `aspect_ratio`
+    pub fn camera(&self, key: &CameraKey, aspect_ratio: f64) -> Result<(Box<dyn Camera>, f64)> {
+        let view = View::new(key.lookfrom, key.lookat, key.vup).with_shutter(0.0, 1.0);
+        let vfov = key.vfov;
+        let ipd = self.ipd;
+        let convergence = self.convergence.unwrap_or(key.focus_distance);
+        let camera: (Box<dyn Camera>, f64) = match self.projection {
+            Projection::Perspective => {
+                let shape = match (&self.aperture_mask, self.blades) {
+                    (Some(path), _) => Aperture::mask(path)?,
+                    (None, Some(blades)) => Aperture::polygon(blades, self.blade_rotation),
+                    (None, None) => Aperture::Circle,
+                };
+                let focus_distance = self.focus_distance.unwrap_or(key.focus_distance);
+                let camera = PerspectiveCamera::new(view, vfov, aspect_ratio, self.aperture, focus_distance)
+                    .with_aperture(shape)
+                    .with_cat_eye(self.cat_eye)
+                    .with_chromatic_aberration(self.chromatic_aberration);
+                (Box::new(camera), aspect_ratio)
+            }
+            Projection::Orthographic => (
+                Box::new(OrthographicCamera::new(view, self.ortho_height, aspect_ratio)),
+                aspect_ratio,
+            ),
+            Projection::Fisheye(mapping) => (
+                Box::new(FisheyeCamera::new(view, self.fov.unwrap_or(180.0), aspect_ratio, mapping)),
+                aspect_ratio,
+            ),
+            Projection::Equirectangular => (Box::new(EquirectangularCamera::new(view)), 2.0),
+            Projection::Cylindrical => (
+                Box::new(CylindricalCamera::new(view, self.hfov.unwrap_or(180.0), self.fov.unwrap_or(60.0))),
+                aspect_ratio,
+            ),
+            Projection::Eye(eye) => (
+                Box::new(PerspectiveCamera::for_eye(view, vfov, aspect_ratio, eye, ipd, convergence)),
+                aspect_ratio,
+            ),
+            Projection::Stereo => (
+                Box::new(StereoCamera::new(view, vfov, aspect_ratio, ipd, convergence)),
+                2.0 * aspect_ratio,
+            ),
+            Projection::Ods => (Box::new(OdsCamera::new(view, ipd)), 1.0),
+        };
+        Ok(camera)
+    }
+}
@@ -0,0 +1,209 @@
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+// Segment from `a` to `b` swept by a sphere of radius `radius`: a cylinder
+// closed by two hemispheres. With `a` and `b` at the same point it is a sphere.
+pub struct Capsule {
+    a: Point3,
+    b: Point3,
+    radius: f64,
+    // Unit direction from `a` to `b`, any direction for a sphere
+    axis: Vec3,
+    mat: Rc<dyn Material>,
+}
+
+impl Capsule {
+    pub fn new(a: Point3, b: Point3, radius: f64, mat: Rc<dyn Material>) -> Capsule {
+        let ba = b - a;
+        let axis = if ba.length_squared() > 0.0 {
+            vec3::unit_vector(ba)
+        } else {
+            Vec3::new(0.0, 1.0, 0.0)
+        };
+        Capsule { a, b, radius, axis, mat }
+    }
+
+    // Sorted intersections of the ray line with the capsule surface
+    fn roots(&self, r: &Ray) -> Vec<f64> {
+        let ba = self.b - self.a;
+        let baba = vec3::dot(ba, ba);
+        let d = r.direction();
+        let mut roots = Vec::new();
+
+        // Cylindrical body, kept where the projection on the segment lies inside it
+        let oa = r.origin() - self.a;
+        let bard = vec3::dot(ba, d);
+        let baoa = vec3::dot(ba, oa);
+        let a = baba * d.length_squared() - bard * bard;
+        let half_b = baba * vec3::dot(oa, d) - baoa * bard;
+        let c = baba * oa.length_squared() - baoa * baoa - self.radius * self.radius * baba;
+        if a.abs() > 1.0e-12 {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrt_d = f64::sqrt(discriminant);
+                for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a] {
+                    let y = baoa + t * bard;
+                    if y > 0.0 && y < baba {
+                        roots.push(t);
+                    }
+                }
+            }
+        }
+
+        // Hemispherical ends, kept on the outer side of each end point. The
+        // body is empty for a sphere, whose two halves are these ends.
+        for (center, outer_side) in [(self.a, -1.0), (self.b, 1.0)] {
+            let oc = r.origin() - center;
+            let a = d.length_squared();
+            let half_b = vec3::dot(oc, d);
+            let c = oc.length_squared() - self.radius * self.radius;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrt_d = f64::sqrt(discriminant);
+                for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a] {
+                    if outer_side * vec3::dot(r.at(t) - center, self.axis) >= 0.0 {
+                        roots.push(t);
+                    }
+                }
+            }
+        }
+
+        roots.sort_by(|x, y| x.total_cmp(y));
+        roots
+    }
+
+    fn fill_record(&self, r: &Ray, t: f64, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(t);
+
+        let length = (self.b - self.a).length();
+        let axis = self.axis;
+        let h = common::clamp(vec3::dot(rec.p - self.a, axis), 0.0, length);
+        let outward_normal = (rec.p - (self.a + h * axis)) / self.radius;
+        rec.set_face_normal(r, outward_normal);
+
+        // u goes around the axis, v along it from one tip to the other
+        let helper = if axis.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let side = vec3::unit_vector(vec3::cross(helper, axis));
+        let up = vec3::cross(axis, side);
+        let phi = f64::atan2(vec3::dot(outward_normal, up), vec3::dot(outward_normal, side));
+        rec.u = (phi + common::PI) / (2.0 * common::PI);
+        rec.v = (vec3::dot(rec.p - self.a, axis) + self.radius) / (length + 2.0 * self.radius);
+        rec.set_tangent_frame(vec3::cross(axis, outward_normal));
+        rec.mat = Some(self.mat.clone());
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Capsule);
+        match self.roots(r).into_iter().find(|&t| t > t_min && t < t_max) {
+            Some(t) => {
+                self.fill_record(r, t, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(self.a, self.b).padded(self.radius);
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        // The capsule is convex: the first and last roots bound the only span
+        let roots = self.roots(r);
+        let (Some(&t0), Some(&t1)) = (roots.first(), roots.last()) else {
+            return Vec::new();
+        };
+        if t1 <= t0 {
+            return Vec::new();
+        }
+
+        let mut span = Span::default();
+        self.fill_record(r, t0, &mut span.enter);
+        self.fill_record(r, t1, &mut span.exit);
+        vec![span]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_box, assert_close, assert_vec_close, material};
+
+    // Vertical capsule of radius 0.5 from y = -0.5 to y = 2.5
+    fn capsule() -> Capsule {
+        Capsule::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 2.0, 0.0), 0.5, material())
+    }
+
+    #[test]
+    fn hit_body_and_tip() {
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Point3::new(-5.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(capsule().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 4.5);
+        assert_vec_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(rec.front_face);
+
+        let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(capsule().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 2.5);
+        assert_vec_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn hit_tangent_to_the_body() {
+        let r = Ray::new(Point3::new(-5.0, 1.0, 0.5), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(capsule().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 5.0);
+    }
+
+    #[test]
+    fn span_from_tip_to_tip() {
+        let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let spans = capsule().hit_intervals(&r);
+        assert_eq!(spans.len(), 1);
+        assert_close(spans[0].enter.t, 2.5);
+        assert_close(spans[0].exit.t, 5.5);
+
+        let miss = Ray::new(Point3::new(-5.0, 1.0, 1.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(capsule().hit_intervals(&miss).is_empty());
+    }
+
+    #[test]
+    fn degenerate_segment_is_a_sphere() {
+        let sphere = Capsule::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 0.0), 1.0, material());
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(sphere.hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 4.0);
+        assert_vec_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(rec.u.is_finite() && rec.v.is_finite());
+
+        let spans = sphere.hit_intervals(&r);
+        assert_eq!(spans.len(), 1);
+        assert_close(spans[0].enter.t, 4.0);
+        assert_close(spans[0].exit.t, 6.0);
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut output_box = Aabb::default();
+        assert!(capsule().bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 2.5, 0.5));
+    }
+}
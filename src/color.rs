@@ -1,4 +1,4 @@
-use std::io::Write;
+use std::io::{self, Write};
  
 use crate::common;
 use crate::vec3::Vec3;
@@ -6,7 +6,13 @@ use crate::vec3::Vec3;
 // Type alias
 pub type Color = Vec3;
  
-pub fn write_color(out: &mut impl Write, pixel_color: Color, samples_per_pixel: i32) {
+pub fn write_color(out: &mut impl Write, pixel_color: Color, samples_per_pixel: i32) -> io::Result<()> {
+    let [r, g, b] = to_rgb8(pixel_color, samples_per_pixel);
+    writeln!(out, "{} {} {}", r, g, b)
+}
+ 
+// 8-bit value of each component of a sum of samples
+pub fn to_rgb8(pixel_color: Color, samples_per_pixel: i32) -> [u8; 3] {
     let mut r = pixel_color.x();
     let mut g = pixel_color.y();
     let mut b = pixel_color.z();
@@ -17,13 +23,10 @@ pub fn write_color(out: &mut impl Write, pixel_color: Color, samples_per_pixel:
     g = f64::sqrt(scale * g);
     b = f64::sqrt(scale * b);
  
-    // Write the translated [0, 255] value of each color component
-    writeln!(
-        out,
-        "{} {} {}",
-        (256.0 * common::clamp(r, 0.0, 0.999)) as i32,
-        (256.0 * common::clamp(g, 0.0, 0.999)) as i32,
-        (256.0 * common::clamp(b, 0.0, 0.999)) as i32,
-    )
-    .expect("writing color");
+    // Translated [0, 255] value of each color component
+    [
+        (256.0 * common::clamp(r, 0.0, 0.999)) as u8,
+        (256.0 * common::clamp(g, 0.0, 0.999)) as u8,
+        (256.0 * common::clamp(b, 0.0, 0.999)) as u8,
+    ]
 }
\ No newline at end of file
@@ -1,9 +1,12 @@
-use rand::Rng;
+use std::cell::RefCell;
+ 
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
  
 // Constants
  
 pub use std::f64::consts::PI;
-pub use std::f64::INFINITY;
+pub const INFINITY: f64 = f64::INFINITY;
  
 // Utility functions
  
@@ -11,9 +14,48 @@ pub fn degrees_to_radians(degrees: f64) -> f64 {
     degrees * PI / 180.0
 }
  
+// Random numbers come from a seekable generator whose state can be saved in
+// a checkpoint, so that a resumed render goes on with the same sequence
+thread_local! {
+    static RNG: RefCell<ChaCha8Rng> = RefCell::new(ChaCha8Rng::from_entropy());
+}
+ 
+#[derive(Clone, Copy)]
+pub struct RngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    pub word_pos: u128,
+}
+ 
+pub fn rng_state() -> RngState {
+    RNG.with(|rng| {
+        let rng = rng.borrow();
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    })
+}
+ 
+// Restart the random sequence of this thread from `seed`, for renders which
+// must come out the same every time, such as the regression tests
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = ChaCha8Rng::seed_from_u64(seed))
+}
+ 
+pub fn set_rng_state(state: &RngState) {
+    RNG.with(|rng| {
+        let mut restored = ChaCha8Rng::from_seed(state.seed);
+        restored.set_stream(state.stream);
+        restored.set_word_pos(state.word_pos);
+        *rng.borrow_mut() = restored;
+    })
+}
+ 
 pub fn random_double() -> f64 {
     // Return a random real in [0.0, 1.0)
-    rand::thread_rng().gen()
+    RNG.with(|rng| rng.borrow_mut().gen())
 }
  
 pub fn random_double_range(min: f64, max: f64) -> f64 {
@@ -0,0 +1,233 @@
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{Point3, Vec3};
+
+// Which part of the cone a candidate intersection lies on
+#[derive(Clone, Copy)]
+enum Surface {
+    Side,
+    BottomCap,
+    TopCap,
+}
+
+// Capped cone or frustum standing on the XZ plane: the radius goes linearly from
+// `bottom_radius` at `base` to `top_radius` at `base + height` along Y. A top
+// radius of zero gives a pointed cone.
+pub struct Cone {
+    base: Point3,
+    bottom_radius: f64,
+    top_radius: f64,
+    height: f64,
+    mat: Rc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(base: Point3, bottom_radius: f64, top_radius: f64, height: f64, mat: Rc<dyn Material>) -> Cone {
+        Cone {
+            base,
+            bottom_radius,
+            top_radius,
+            height,
+            mat,
+        }
+    }
+
+    // Rate at which the radius changes with height
+    fn slope(&self) -> f64 {
+        (self.top_radius - self.bottom_radius) / self.height
+    }
+
+    fn radius_at(&self, y: f64) -> f64 {
+        self.bottom_radius + self.slope() * y
+    }
+
+    // All intersections of the ray line with the cone, sorted by t
+    fn candidates(&self, r: &Ray) -> Vec<(f64, Surface)> {
+        let o = r.origin() - self.base;
+        let d = r.direction();
+        let k = self.slope();
+        let r0 = self.radius_at(o.y());
+
+        let mut hits = Vec::new();
+        let in_height = |t: f64| {
+            let y = o.y() + t * d.y();
+            (0.0..=self.height).contains(&y)
+        };
+
+        // Side: x^2 + z^2 = (r0 + k y)^2
+        let a = d.x() * d.x() + d.z() * d.z() - k * k * d.y() * d.y();
+        let half_b = o.x() * d.x() + o.z() * d.z() - k * d.y() * r0;
+        let c = o.x() * o.x() + o.z() * o.z() - r0 * r0;
+        if a.abs() > 1.0e-12 {
+            let discriminant = half_b * half_b - a * c;
+            // A tangent root is kept: it is the apex for a ray along the axis
+            if discriminant >= 0.0 {
+                let sqrt_d = f64::sqrt(discriminant);
+                for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a] {
+                    if in_height(t) {
+                        hits.push((t, Surface::Side));
+                    }
+                }
+            }
+        } else if half_b.abs() > 1.0e-12 {
+            let t = -c / (2.0 * half_b);
+            if in_height(t) {
+                hits.push((t, Surface::Side));
+            }
+        }
+
+        // Caps
+        if d.y() != 0.0 {
+            for (surface, y, radius) in [
+                (Surface::BottomCap, 0.0, self.bottom_radius),
+                (Surface::TopCap, self.height, self.top_radius),
+            ] {
+                let t = (y - o.y()) / d.y();
+                let p = o + t * d;
+                if radius > 0.0 && p.x() * p.x() + p.z() * p.z() <= radius * radius {
+                    hits.push((t, surface));
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+        hits
+    }
+
+    fn fill_record(&self, r: &Ray, t: f64, surface: Surface, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(t);
+        let local = rec.p - self.base;
+
+        let outward_normal = match surface {
+            Surface::Side => {
+                let radial = Vec3::new(local.x(), 0.0, local.z());
+                let rho = radial.length().max(1.0e-12);
+                Vec3::new(local.x() / rho, -self.slope(), local.z() / rho).unit_vector()
+            }
+            Surface::BottomCap => Vec3::new(0.0, -1.0, 0.0),
+            Surface::TopCap => Vec3::new(0.0, 1.0, 0.0),
+        };
+        rec.set_face_normal(r, outward_normal);
+
+        match surface {
+            Surface::Side => {
+                let phi = f64::atan2(local.z(), local.x());
+                rec.u = (phi + common::PI) / (2.0 * common::PI);
+                rec.v = local.y() / self.height;
+                rec.set_tangent_frame(Vec3::new(-local.z(), 0.0, local.x()));
+            }
+            Surface::BottomCap | Surface::TopCap => {
+                let radius = self.bottom_radius.max(self.top_radius);
+                rec.u = local.x() / (2.0 * radius) + 0.5;
+                rec.v = local.z() / (2.0 * radius) + 0.5;
+                rec.set_tangent_frame(Vec3::new(1.0, 0.0, 0.0));
+            }
+        }
+        rec.mat = Some(self.mat.clone());
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Cone);
+        match self.candidates(r).into_iter().find(|&(t, _)| t > t_min && t < t_max) {
+            Some((t, surface)) => {
+                self.fill_record(r, t, surface, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let radius = self.bottom_radius.max(self.top_radius);
+        *output_box = Aabb::new(
+            self.base - Vec3::new(radius, 0.0, radius),
+            self.base + Vec3::new(radius, self.height, radius),
+        );
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        // The cone is convex: the first and last intersections bound the only span
+        let hits = self.candidates(r);
+        let (Some(&(t0, s0)), Some(&(t1, s1))) = (hits.first(), hits.last()) else {
+            return Vec::new();
+        };
+        if t1 <= t0 {
+            return Vec::new();
+        }
+
+        let mut span = Span::default();
+        self.fill_record(r, t0, s0, &mut span.enter);
+        self.fill_record(r, t1, s1, &mut span.exit);
+        vec![span]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_box, assert_close, assert_vec_close, material};
+
+    // Pointed cone of radius 1 and height 2
+    fn cone() -> Cone {
+        Cone::new(Point3::new(0.0, 0.0, 0.0), 1.0, 0.0, 2.0, material())
+    }
+
+    #[test]
+    fn hit_side() {
+        let r = Ray::new(Point3::new(-5.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(cone().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 4.5);
+        assert_vec_close(rec.normal, Vec3::new(-1.0, 0.5, 0.0).unit_vector());
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    fn hit_apex_along_the_axis() {
+        let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(cone().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 3.0);
+
+        let spans = cone().hit_intervals(&r);
+        assert_eq!(spans.len(), 1);
+        assert_close(spans[0].enter.t, 3.0);
+        assert_close(spans[0].exit.t, 5.0);
+        assert_vec_close(spans[0].exit.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!(!spans[0].exit.front_face);
+    }
+
+    #[test]
+    fn hit_top_cap_of_a_frustum() {
+        let frustum = Cone::new(Point3::new(0.0, 0.0, 0.0), 1.0, 0.5, 2.0, material());
+        let r = Ray::new(Point3::new(0.25, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(frustum.hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 3.0);
+        assert_vec_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn miss_above_the_apex() {
+        let r = Ray::new(Point3::new(-5.0, 2.5, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(!cone().hit(&r, 0.001, common::INFINITY, &mut HitRecord::new()));
+        assert!(cone().hit_intervals(&r).is_empty());
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut output_box = Aabb::default();
+        assert!(cone().bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 2.0, 1.0));
+    }
+}
@@ -0,0 +1,201 @@
+use std::fs;
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::common;
+use crate::error::{Error, Result};
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{Point3, Vec3};
+
+// Find the [t_enter, t_exit] interval of the ray inside a closed boundary,
+// clipped to [t_min, t_max]
+fn interval_inside(boundary: &dyn Hittable, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+    let mut rec1 = HitRecord::new();
+    let mut rec2 = HitRecord::new();
+
+    if !boundary.hit(r, -common::INFINITY, common::INFINITY, &mut rec1) {
+        return None;
+    }
+    if !boundary.hit(r, rec1.t + 0.0001, common::INFINITY, &mut rec2) {
+        return None;
+    }
+
+    let t_enter = rec1.t.max(t_min).max(0.0);
+    let t_exit = rec2.t.min(t_max);
+    if t_enter >= t_exit {
+        return None;
+    }
+    Some((t_enter, t_exit))
+}
+
+fn record_scattering(r: &Ray, t: f64, phase_function: &Rc<dyn Material>, rec: &mut HitRecord) {
+    rec.t = t;
+    rec.p = r.at(t);
+    rec.normal = Vec3::new(1.0, 0.0, 0.0); // arbitrary
+    rec.front_face = true; // also arbitrary
+    rec.set_tangent_frame(Vec3::new(0.0, 1.0, 0.0));
+    rec.mat = Some(phase_function.clone());
+}
+
+// Homogeneous participating medium (fog, smoke) filling a closed boundary
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    neg_inv_density: f64,
+    phase_function: Rc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Box<dyn Hittable>, density: f64, phase_function: Rc<dyn Material>) -> ConstantMedium {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Medium);
+        let Some((t_enter, t_exit)) = interval_inside(self.boundary.as_ref(), r, t_min, t_max) else {
+            return false;
+        };
+
+        let ray_length = r.direction().length();
+        let distance_inside_boundary = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * f64::ln(common::random_double());
+        if hit_distance > distance_inside_boundary {
+            return false;
+        }
+
+        record_scattering(r, t_enter + hit_distance / ray_length, &self.phase_function, rec);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        self.boundary.bounding_box(output_box)
+    }
+}
+
+// Density values on a regular grid stretched over an axis aligned box
+pub struct VoxelGrid {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    min: Point3,
+    max: Point3,
+    values: Vec<f64>,
+}
+
+impl VoxelGrid {
+    // Load a headerless raw file of nx * ny * nz unsigned bytes, x varying fastest.
+    // Bytes are mapped to densities in [0, 1].
+    pub fn load_raw(path: &str, nx: usize, ny: usize, nz: usize, min: Point3, max: Point3) -> Result<VoxelGrid> {
+        let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+        if bytes.len() != nx * ny * nz {
+            let message = format!("expected {} voxels, found {}", nx * ny * nz, bytes.len());
+            return Err(Error::invalid_file(path, &message));
+        }
+        let values = bytes.iter().map(|&b| b as f64 / 255.0).collect();
+        Ok(VoxelGrid { nx, ny, nz, min, max, values })
+    }
+
+    fn voxel(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.values[(k * self.ny + j) * self.nx + i]
+    }
+
+    fn max_value(&self) -> f64 {
+        self.values.iter().cloned().fold(0.0, f64::max)
+    }
+
+    // Trilinearly interpolated density at a point, zero outside the grid
+    pub fn density(&self, p: &Point3) -> f64 {
+        let extent = self.max - self.min;
+        let local = *p - self.min;
+        let coords = [
+            (local.x() / extent.x(), self.nx),
+            (local.y() / extent.y(), self.ny),
+            (local.z() / extent.z(), self.nz),
+        ];
+
+        let mut cells = [(0, 0, 0.0); 3];
+        for (cell, &(f, n)) in cells.iter_mut().zip(coords.iter()) {
+            if !(0.0..=1.0).contains(&f) {
+                return 0.0;
+            }
+            // Voxel values sit at cell centers
+            let x = (f * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i0 = x.floor() as usize;
+            *cell = (i0, (i0 + 1).min(n - 1), x - i0 as f64);
+        }
+
+        let [(i0, i1, fx), (j0, j1, fy), (k0, k1, fz)] = cells;
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let c00 = lerp(self.voxel(i0, j0, k0), self.voxel(i1, j0, k0), fx);
+        let c10 = lerp(self.voxel(i0, j1, k0), self.voxel(i1, j1, k0), fx);
+        let c01 = lerp(self.voxel(i0, j0, k1), self.voxel(i1, j0, k1), fx);
+        let c11 = lerp(self.voxel(i0, j1, k1), self.voxel(i1, j1, k1), fx);
+        lerp(lerp(c00, c10, fy), lerp(c01, c11, fy), fz)
+    }
+}
+
+// Heterogeneous medium whose density comes from a voxel grid, sampled with
+// delta (Woodcock) tracking against the grid's maximum density
+pub struct GridMedium {
+    boundary: Box<dyn Hittable>,
+    grid: VoxelGrid,
+    density_scale: f64,
+    max_density: f64,
+    phase_function: Rc<dyn Material>,
+}
+
+impl GridMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        grid: VoxelGrid,
+        density_scale: f64,
+        phase_function: Rc<dyn Material>,
+    ) -> GridMedium {
+        let max_density = grid.max_value() * density_scale;
+        GridMedium {
+            boundary,
+            grid,
+            density_scale,
+            max_density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Medium);
+        if self.max_density <= 0.0 {
+            return false;
+        }
+        let Some((t_enter, t_exit)) = interval_inside(self.boundary.as_ref(), r, t_min, t_max) else {
+            return false;
+        };
+
+        let ray_length = r.direction().length();
+        let mut t = t_enter;
+        loop {
+            t -= f64::ln(1.0 - common::random_double()) / (self.max_density * ray_length);
+            if t >= t_exit {
+                return false;
+            }
+            let density = self.grid.density(&r.at(t)) * self.density_scale;
+            if common::random_double() < density / self.max_density {
+                record_scattering(r, t, &self.phase_function, rec);
+                return true;
+            }
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        self.boundary.bounding_box(output_box)
+    }
+}
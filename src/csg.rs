@@ -0,0 +1,273 @@
+// Constructive solid geometry: boolean combinations of closed objects.
+//
+// Both operands report the spans of the ray inside them; the spans are merged
+// by walking the sorted entry/exit events and keeping the parts where the
+// boolean operation is true.
+
+use crate::aabb::{self, Aabb};
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    fn apply(self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => inside_left || inside_right,
+            CsgOperation::Intersection => inside_left && inside_right,
+            CsgOperation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+pub struct Csg {
+    operation: CsgOperation,
+    left: Box<dyn Hittable>,
+    right: Box<dyn Hittable>,
+    bbox: Option<Aabb>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Csg {
+        let mut left_box = Aabb::default();
+        let mut right_box = Aabb::default();
+        let left_bounded = left.bounding_box(&mut left_box);
+        let right_bounded = right.bounding_box(&mut right_box);
+        let bbox = match operation {
+            CsgOperation::Union if left_bounded && right_bounded => {
+                Some(aabb::surrounding_box(&left_box, &right_box))
+            }
+            CsgOperation::Intersection if left_bounded && right_bounded => {
+                Some(aabb::overlapping_box(&left_box, &right_box))
+            }
+            CsgOperation::Intersection if left_bounded => Some(left_box),
+            CsgOperation::Intersection if right_bounded => Some(right_box),
+            CsgOperation::Difference if left_bounded => Some(left_box),
+            _ => None,
+        };
+
+        Csg {
+            operation,
+            left,
+            right,
+            bbox,
+        }
+    }
+
+    pub fn union(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Csg {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Csg {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Csg {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+}
+
+struct Event {
+    rec: HitRecord,
+    from_left: bool,
+    entering: bool,
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Csg);
+        // Gathering every span is costly, so reject rays missing the bounds first
+        if let Some(bbox) = &self.bbox {
+            if !bbox.hit(r, t_min, t_max) {
+                return false;
+            }
+        }
+
+        for span in self.hit_intervals(r) {
+            for boundary in [span.enter, span.exit] {
+                if boundary.t > t_min && boundary.t < t_max {
+                    *rec = boundary;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        match self.bbox {
+            Some(bbox) => {
+                *output_box = bbox;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        let mut events = Vec::new();
+        for (from_left, object) in [(true, &self.left), (false, &self.right)] {
+            for span in object.hit_intervals(r) {
+                events.push(Event { rec: span.enter, from_left, entering: true });
+                events.push(Event { rec: span.exit, from_left, entering: false });
+            }
+        }
+        events.sort_by(|a, b| a.rec.t.total_cmp(&b.rec.t));
+
+        let mut spans = Vec::new();
+        let mut enter: Option<HitRecord> = None;
+        let (mut inside_left, mut inside_right) = (false, false);
+        for mut event in events {
+            let was_inside = self.operation.apply(inside_left, inside_right);
+            if event.from_left {
+                inside_left = event.entering;
+            } else {
+                inside_right = event.entering;
+            }
+            let is_inside = self.operation.apply(inside_left, inside_right);
+            if was_inside == is_inside {
+                continue;
+            }
+
+            // A subtracted object contributes its surface turned inside out. The
+            // normal already faces the ray, so only the side it was hit from changes.
+            if self.operation == CsgOperation::Difference && !event.from_left {
+                event.rec.front_face = !event.rec.front_face;
+            }
+
+            if is_inside {
+                enter = Some(event.rec);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span { enter, exit: event.rec });
+            }
+        }
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common;
+    use crate::sphere::Sphere;
+    use crate::testing::{assert_box, assert_close, assert_vec_close, material};
+    use crate::vec3::{Point3, Vec3};
+
+    fn sphere(x: f64, radius: f64) -> Box<dyn Hittable> {
+        Box::new(Sphere::new(Point3::new(x, 0.0, 0.0), radius, material()))
+    }
+
+    // Unit spheres overlapping over x in [-0.5, 0.5]
+    fn overlapping(operation: CsgOperation) -> Csg {
+        Csg::new(operation, sphere(-0.5, 1.0), sphere(0.5, 1.0))
+    }
+
+    // Along the x axis, reaching x = 0 at t = 5
+    fn ray_along_x() -> Ray {
+        Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0)
+    }
+
+    fn assert_spans(spans: &[Span], expected: &[(f64, f64)]) {
+        assert_eq!(spans.len(), expected.len());
+        for (span, &(enter, exit)) in spans.iter().zip(expected) {
+            assert_close(span.enter.t, enter);
+            assert_close(span.exit.t, exit);
+        }
+    }
+
+    // Every boundary faces the ray; only the side it is crossed from changes
+    fn assert_sides(span: &Span, enter_front: bool, exit_front: bool) {
+        for (boundary, front_face) in [(&span.enter, enter_front), (&span.exit, exit_front)] {
+            assert_vec_close(boundary.normal, Vec3::new(-1.0, 0.0, 0.0));
+            assert_eq!(boundary.front_face, front_face);
+        }
+    }
+
+    #[test]
+    fn union_spans_both_operands() {
+        let spans = overlapping(CsgOperation::Union).hit_intervals(&ray_along_x());
+        assert_spans(&spans, &[(3.5, 6.5)]);
+        assert_sides(&spans[0], true, false);
+    }
+
+    #[test]
+    fn intersection_keeps_the_overlap() {
+        let spans = overlapping(CsgOperation::Intersection).hit_intervals(&ray_along_x());
+        assert_spans(&spans, &[(4.5, 5.5)]);
+        assert_sides(&spans[0], true, false);
+    }
+
+    #[test]
+    fn difference_turns_the_subtracted_surface_inside_out() {
+        let spans = overlapping(CsgOperation::Difference).hit_intervals(&ray_along_x());
+        assert_spans(&spans, &[(3.5, 4.5)]);
+        // Where the ray enters the right sphere it leaves the difference
+        assert_sides(&spans[0], true, false);
+    }
+
+    #[test]
+    fn hit_returns_the_first_boundary_after_t_min() {
+        let csg = overlapping(CsgOperation::Difference);
+        let mut rec = HitRecord::new();
+        assert!(csg.hit(&ray_along_x(), 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 3.5);
+        assert!(rec.front_face);
+
+        assert!(csg.hit(&ray_along_x(), 4.0, common::INFINITY, &mut rec));
+        assert_close(rec.t, 4.5);
+        assert!(!rec.front_face);
+
+        assert!(!csg.hit(&ray_along_x(), 5.0, common::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn ray_starting_inside_an_operand() {
+        // Inside the left sphere only, so inside the difference
+        let r = Ray::new(Point3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let csg = overlapping(CsgOperation::Difference);
+        assert_spans(&csg.hit_intervals(&r), &[(-0.5, 0.5)]);
+
+        let mut rec = HitRecord::new();
+        assert!(csg.hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 0.5);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn nested_operations() {
+        // Union of both spheres with a hole through the middle
+        let union = Box::new(overlapping(CsgOperation::Union));
+        let csg = Csg::difference(union, sphere(0.0, 0.25));
+        let spans = csg.hit_intervals(&ray_along_x());
+        assert_spans(&spans, &[(3.5, 4.75), (5.25, 6.5)]);
+        assert_sides(&spans[0], true, false);
+        assert_sides(&spans[1], true, false);
+    }
+
+    #[test]
+    fn miss() {
+        let r = Ray::new(Point3::new(-5.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        for operation in [CsgOperation::Union, CsgOperation::Intersection, CsgOperation::Difference] {
+            let csg = overlapping(operation);
+            assert!(csg.hit_intervals(&r).is_empty());
+            assert!(!csg.hit(&r, 0.001, common::INFINITY, &mut HitRecord::new()));
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut output_box = Aabb::default();
+        assert!(overlapping(CsgOperation::Union).bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-1.5, -1.0, -1.0), Vec3::new(1.5, 1.0, 1.0));
+        assert!(overlapping(CsgOperation::Intersection).bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-0.5, -1.0, -1.0), Vec3::new(0.5, 1.0, 1.0));
+        assert!(overlapping(CsgOperation::Difference).bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-1.5, -1.0, -1.0), Vec3::new(0.5, 1.0, 1.0));
+    }
+}
@@ -1,5 +1,8 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
 use crate::ray::Ray;
+use crate::stats::{self, Primitive};
 use crate::vec3::{Point3, Vec3};
 use std::rc::Rc;
 use crate::material::Material;
@@ -14,10 +17,11 @@ impl Cube {
     pub fn new(min: Point3, max: Point3, mat: Rc<dyn Material>) -> Cube {
         Cube { min, max, mat }
     }
-}
 
-impl Hittable for Cube {
-    fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64, rec: &mut HitRecord) -> bool {
+    // Intervalle [t_near, t_far] pendant lequel la droite du rayon est dans le cube
+    fn slab_interval(&self, r: &Ray) -> Option<(f64, f64)> {
+        let mut t_near = -common::INFINITY;
+        let mut t_far = common::INFINITY;
         for a in 0..3 {
             // Accès explicite aux composantes de Vec3
             let inv_d = if a == 0 {
@@ -47,33 +51,87 @@ impl Hittable for Cube {
             if inv_d < 0.0 {
                 std::mem::swap(&mut t0, &mut t1);
             }
-            t_min = t_min.max(t0);
-            t_max = t_max.min(t1);
-            if t_max <= t_min {
-                return false;
+            t_near = t_near.max(t0);
+            t_far = t_far.min(t1);
+            if t_far <= t_near {
+                return None;
             }
         }
 
-        rec.t = t_min;
+        Some((t_near, t_far))
+    }
+
+    fn fill_record(&self, r: &Ray, t: f64, rec: &mut HitRecord) {
+        rec.t = t;
         rec.p = r.at(rec.t);
 
-        // Calculer la normale en fonction de la face touchée
+        // Coordonnées locales normalisées dans [0, 1] pour le calcul des UV
+        let size = self.max - self.min;
+        let local = rec.p - self.min;
+        let (lx, ly, lz) = (local.x() / size.x(), local.y() / size.y(), local.z() / size.z());
+
+        // Calculer la normale, les UV et la tangente en fonction de la face touchée
+        let mut dpdu = Vec3::new(1.0, 0.0, 0.0);
         if rec.p.x() <= self.min.x() + 1e-4 {
             rec.normal = Vec3::new(-1.0, 0.0, 0.0);
+            (rec.u, rec.v) = (lz, ly);
+            dpdu = Vec3::new(0.0, 0.0, 1.0);
         } else if rec.p.x() >= self.max.x() - 1e-4 {
             rec.normal = Vec3::new(1.0, 0.0, 0.0);
+            (rec.u, rec.v) = (1.0 - lz, ly);
+            dpdu = Vec3::new(0.0, 0.0, -1.0);
         } else if rec.p.y() <= self.min.y() + 1e-4 {
             rec.normal = Vec3::new(0.0, -1.0, 0.0);
+            (rec.u, rec.v) = (lx, lz);
         } else if rec.p.y() >= self.max.y() - 1e-4 {
             rec.normal = Vec3::new(0.0, 1.0, 0.0);
+            (rec.u, rec.v) = (lx, 1.0 - lz);
         } else if rec.p.z() <= self.min.z() + 1e-4 {
             rec.normal = Vec3::new(0.0, 0.0, -1.0);
+            (rec.u, rec.v) = (1.0 - lx, ly);
+            dpdu = Vec3::new(-1.0, 0.0, 0.0);
         } else if rec.p.z() >= self.max.z() - 1e-4 {
             rec.normal = Vec3::new(0.0, 0.0, 1.0);
+            (rec.u, rec.v) = (lx, ly);
         }
 
         rec.set_face_normal(r, rec.normal);
+        rec.set_tangent_frame(dpdu);
         rec.mat = Some(self.mat.clone());
+    }
+}
+
+impl Hittable for Cube {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Cube);
+        let Some((t_near, t_far)) = self.slab_interval(r) else {
+            return false;
+        };
+
+        // Si le rayon part de l'intérieur du cube, c'est la sortie qui compte
+        let t = if t_near > t_min && t_near < t_max {
+            t_near
+        } else if t_far > t_min && t_far < t_max {
+            t_far
+        } else {
+            return false;
+        };
+        self.fill_record(r, t, rec);
         true
     }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(self.min, self.max);
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        let Some((t_near, t_far)) = self.slab_interval(r) else {
+            return Vec::new();
+        };
+        let mut span = Span::default();
+        self.fill_record(r, t_near, &mut span.enter);
+        self.fill_record(r, t_far, &mut span.exit);
+        vec![span]
+    }
 }
@@ -1,6 +1,9 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::common;
 use crate::ray::Ray;
-use crate::vec3::{Vec3, Point3};
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Vec3, Point3};
 use std::rc::Rc;
 use crate::material::Material;
 
@@ -16,10 +19,50 @@ impl Cylinder {
     pub fn new(base: Point3, axis: Vec3, radius: f64, height: f64, mat: Rc<dyn Material>) -> Cylinder {
         Cylinder { base, axis, radius, height, mat }
     }
+
+    // Coordonnées UV d'un point sur l'un des disques de base
+    fn cap_uv(&self, p: &Point3) -> (f64, f64) {
+        (
+            (p.x() - self.base.x()) / (2.0 * self.radius) + 0.5,
+            (p.z() - self.base.z()) / (2.0 * self.radius) + 0.5,
+        )
+    }
+
+    fn inside_cap(&self, p: &Point3) -> bool {
+        (p.x() - self.base.x()).powi(2) + (p.z() - self.base.z()).powi(2) <= self.radius * self.radius
+    }
+
+    fn fill_side_record(&self, r: &Ray, t: f64, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(rec.t);
+        let outward_normal = Vec3::new(rec.p.x() - self.base.x(), 0.0, rec.p.z() - self.base.z()).unit_vector();
+        rec.set_face_normal(r, outward_normal);
+        // u suit l'angle autour de l'axe, v la hauteur
+        let phi = f64::atan2(outward_normal.z(), outward_normal.x());
+        rec.u = (phi + common::PI) / (2.0 * common::PI);
+        rec.v = (rec.p.y() - self.base.y()) / self.height;
+        rec.set_tangent_frame(vec3::cross(outward_normal, self.axis));
+        rec.mat = Some(self.mat.clone());
+    }
+
+    fn fill_cap_record(&self, r: &Ray, t: f64, top: bool, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(rec.t);
+        let outward_normal = if top {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(0.0, -1.0, 0.0)
+        };
+        rec.set_face_normal(r, outward_normal);
+        (rec.u, rec.v) = self.cap_uv(&rec.p);
+        rec.set_tangent_frame(Vec3::new(1.0, 0.0, 0.0));
+        rec.mat = Some(self.mat.clone());
+    }
 }
 
 impl Hittable for Cylinder {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Cylinder);
         let oc = r.origin() - self.base;
         let dir = r.direction();
 
@@ -39,11 +82,7 @@ impl Hittable for Cylinder {
             if t >= t_min && t <= t_max {
                 let y = r.origin().y() + t * r.direction().y();
                 if y >= self.base.y() && y <= self.base.y() + self.height {
-                    rec.t = t;
-                    rec.p = r.at(rec.t);
-                    let outward_normal = Vec3::new(rec.p.x() - self.base.x(), 0.0, rec.p.z() - self.base.z()).unit_vector();
-                    rec.set_face_normal(r, outward_normal);
-                    rec.mat = Some(self.mat.clone());
+                    self.fill_side_record(r, t, rec);
                     return true;
                 }
             }
@@ -53,11 +92,8 @@ impl Hittable for Cylinder {
         let t_base1 = (self.base.y() - r.origin().y()) / r.direction().y();
         if t_base1 >= t_min && t_base1 <= t_max {
             let p_base1 = r.at(t_base1);
-            if (p_base1.x() - self.base.x()).powi(2) + (p_base1.z() - self.base.z()).powi(2) <= self.radius * self.radius {
-                rec.t = t_base1;
-                rec.p = p_base1;
-                rec.set_face_normal(r, Vec3::new(0.0, -1.0, 0.0));
-                rec.mat = Some(self.mat.clone());
+            if self.inside_cap(&p_base1) {
+                self.fill_cap_record(r, t_base1, false, rec);
                 return true;
             }
         }
@@ -66,15 +102,65 @@ impl Hittable for Cylinder {
         let t_base2 = (self.base.y() + self.height - r.origin().y()) / r.direction().y();
         if t_base2 >= t_min && t_base2 <= t_max {
             let p_base2 = r.at(t_base2);
-            if (p_base2.x() - self.base.x()).powi(2) + (p_base2.z() - self.base.z()).powi(2) <= self.radius * self.radius {
-                rec.t = t_base2;
-                rec.p = p_base2;
-                rec.set_face_normal(r, Vec3::new(0.0, 1.0, 0.0));
-                rec.mat = Some(self.mat.clone());
+            if self.inside_cap(&p_base2) {
+                self.fill_cap_record(r, t_base2, true, rec);
                 return true;
             }
         }
 
         false
     }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = Vec3::new(self.radius, 0.0, self.radius);
+        *output_box = Aabb::new(self.base - r, self.base + r + Vec3::new(0.0, self.height, 0.0));
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        let oc = r.origin() - self.base;
+        let dir = r.direction();
+
+        // Toutes les intersections de la droite avec la surface latérale et les bases
+        let mut hits: Vec<(f64, Option<bool>)> = Vec::new();
+        let a = dir.x() * dir.x() + dir.z() * dir.z();
+        let b = 2.0 * (oc.x() * dir.x() + oc.z() * dir.z());
+        let c = oc.x() * oc.x() + oc.z() * oc.z() - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if a > 0.0 && discriminant > 0.0 {
+            let sqrt_discriminant = discriminant.sqrt();
+            for t in [(-b - sqrt_discriminant) / (2.0 * a), (-b + sqrt_discriminant) / (2.0 * a)] {
+                let y = r.at(t).y();
+                if y >= self.base.y() && y <= self.base.y() + self.height {
+                    hits.push((t, None));
+                }
+            }
+        }
+        if dir.y() != 0.0 {
+            for (top, y) in [(false, self.base.y()), (true, self.base.y() + self.height)] {
+                let t = (y - r.origin().y()) / dir.y();
+                if self.inside_cap(&r.at(t)) {
+                    hits.push((t, Some(top)));
+                }
+            }
+        }
+
+        // Le cylindre est convexe : on garde la première et la dernière intersection
+        hits.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let (Some(&first), Some(&last)) = (hits.first(), hits.last()) else {
+            return Vec::new();
+        };
+        if last.0 <= first.0 {
+            return Vec::new();
+        }
+
+        let mut span = Span::default();
+        for ((t, cap), rec) in [(first, &mut span.enter), (last, &mut span.exit)] {
+            match cap {
+                Some(top) => self.fill_cap_record(r, t, top, rec),
+                None => self.fill_side_record(r, t, rec),
+            }
+        }
+        vec![span]
+    }
 }
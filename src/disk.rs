@@ -0,0 +1,139 @@
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::common;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+// Flat disk facing `normal`. A non-zero inner radius turns it into an annulus.
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    inner_radius: f64,
+    tangent: Vec3,
+    bitangent: Vec3,
+    mat: Rc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, mat: Rc<dyn Material>) -> Disk {
+        Disk::annulus(center, normal, radius, 0.0, mat)
+    }
+
+    pub fn annulus(center: Point3, normal: Vec3, radius: f64, inner_radius: f64, mat: Rc<dyn Material>) -> Disk {
+        let normal = vec3::unit_vector(normal);
+        let helper = if normal.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let tangent = vec3::unit_vector(vec3::cross(helper, normal));
+        let bitangent = vec3::cross(normal, tangent);
+
+        Disk {
+            center,
+            normal,
+            radius,
+            inner_radius,
+            tangent,
+            bitangent,
+            mat,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Disk);
+        let denom = vec3::dot(self.normal, r.direction());
+        if denom.abs() < 1.0e-9 {
+            return false;
+        }
+
+        let t = vec3::dot(self.center - r.origin(), self.normal) / denom;
+        if t <= t_min || t >= t_max {
+            return false;
+        }
+
+        let local = r.at(t) - self.center;
+        let x = vec3::dot(local, self.tangent);
+        let y = vec3::dot(local, self.bitangent);
+        let rho = f64::sqrt(x * x + y * y);
+        if rho > self.radius || rho < self.inner_radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_face_normal(r, self.normal);
+        // u goes around the center, v from the inner to the outer edge
+        rec.u = (f64::atan2(y, x) + common::PI) / (2.0 * common::PI);
+        rec.v = (rho - self.inner_radius) / (self.radius - self.inner_radius);
+        rec.set_tangent_frame(self.tangent);
+        rec.mat = Some(self.mat.clone());
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        // Extent of a circle along each axis is radius * sin(angle between axis and normal)
+        let extent = |n: f64| self.radius * f64::sqrt(f64::max(0.0, 1.0 - n * n));
+        let half = Vec3::new(extent(self.normal.x()), extent(self.normal.y()), extent(self.normal.z()));
+        *output_box = Aabb::new(self.center - half, self.center + half).padded(1.0e-4);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_box, assert_close, assert_vec_close, material};
+
+    fn disk() -> Disk {
+        Disk::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), 1.0, material())
+    }
+
+    #[test]
+    fn hit_from_both_sides() {
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Point3::new(0.5, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(disk().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 3.0);
+        assert_vec_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert!(rec.front_face);
+        assert_close(rec.v, 0.5);
+
+        let r = Ray::new(Point3::new(0.5, -3.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        assert!(disk().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_vec_close(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn miss_outside_parallel_and_in_the_hole() {
+        let outside = Ray::new(Point3::new(1.5, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(!disk().hit(&outside, 0.001, common::INFINITY, &mut HitRecord::new()));
+        let parallel = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(!disk().hit(&parallel, 0.001, common::INFINITY, &mut HitRecord::new()));
+
+        let annulus = Disk::annulus(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 1.0, 0.5, material());
+        let hole = Ray::new(Point3::new(0.25, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(!annulus.hit(&hole, 0.001, common::INFINITY, &mut HitRecord::new()));
+    }
+
+    #[test]
+    fn no_spans_for_an_open_surface() {
+        let r = Ray::new(Point3::new(0.5, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(disk().hit_intervals(&r).is_empty());
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut output_box = Aabb::default();
+        assert!(disk().bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-1.0001, -0.0001, -1.0001), Vec3::new(1.0001, 0.0001, 1.0001));
+    }
+}
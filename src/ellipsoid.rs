@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+// Axis-aligned ellipsoid with semi-axes `radii`
+pub struct Ellipsoid {
+    center: Point3,
+    radii: Vec3,
+    mat: Rc<dyn Material>,
+}
+
+impl Ellipsoid {
+    pub fn new(center: Point3, radii: Vec3, mat: Rc<dyn Material>) -> Ellipsoid {
+        Ellipsoid { center, radii, mat }
+    }
+
+    // Scale space so the ellipsoid becomes the unit sphere; t is unchanged by this
+    fn to_unit(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.x() / self.radii.x(), v.y() / self.radii.y(), v.z() / self.radii.z())
+    }
+
+    fn roots(&self, r: &Ray) -> Option<(f64, f64)> {
+        let oc = self.to_unit(r.origin() - self.center);
+        let d = self.to_unit(r.direction());
+        let a = d.length_squared();
+        let half_b = vec3::dot(oc, d);
+        let c = oc.length_squared() - 1.0;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let sqrt_d = f64::sqrt(discriminant);
+        Some(((-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a))
+    }
+
+    fn fill_record(&self, r: &Ray, t: f64, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(t);
+        let unit = self.to_unit(rec.p - self.center);
+
+        // Gradient of the implicit function (x/a)^2 + (y/b)^2 + (z/c)^2 - 1
+        let outward_normal = vec3::unit_vector(self.to_unit(unit));
+        rec.set_face_normal(r, outward_normal);
+
+        // Same parametrization as the sphere, on the unit sphere point
+        let theta = f64::acos(common::clamp(-unit.y(), -1.0, 1.0));
+        let phi = f64::atan2(-unit.z(), unit.x()) + common::PI;
+        rec.u = phi / (2.0 * common::PI);
+        rec.v = theta / common::PI;
+        rec.set_tangent_frame(Vec3::new(
+            self.radii.x() * unit.z(),
+            0.0,
+            -self.radii.z() * unit.x(),
+        ));
+        rec.mat = Some(self.mat.clone());
+    }
+}
+
+impl Hittable for Ellipsoid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Ellipsoid);
+        let Some((t0, t1)) = self.roots(r) else {
+            return false;
+        };
+
+        let t = if t0 > t_min && t0 < t_max {
+            t0
+        } else if t1 > t_min && t1 < t_max {
+            t1
+        } else {
+            return false;
+        };
+        self.fill_record(r, t, rec);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = Aabb::new(self.center - self.radii, self.center + self.radii);
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        let Some((t0, t1)) = self.roots(r) else {
+            return Vec::new();
+        };
+        let mut span = Span::default();
+        self.fill_record(r, t0, &mut span.enter);
+        self.fill_record(r, t1, &mut span.exit);
+        vec![span]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_box, assert_close, assert_vec_close, material};
+
+    fn ellipsoid() -> Ellipsoid {
+        Ellipsoid::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0), material())
+    }
+
+    #[test]
+    fn hit_along_each_radius() {
+        let mut rec = HitRecord::new();
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(ellipsoid().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 3.0);
+        assert_vec_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(rec.front_face);
+
+        let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(ellipsoid().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 4.0);
+        assert_vec_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn normal_is_the_gradient() {
+        // (sqrt(2), sqrt(1/2), 0) lies on the surface, where the gradient is (x/4, y, 0)
+        let (x, y) = (f64::sqrt(2.0), f64::sqrt(0.5));
+        let r = Ray::new(Point3::new(x, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(ellipsoid().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 5.0 - y);
+        assert_vec_close(rec.normal, Vec3::new(x / 4.0, y, 0.0).unit_vector());
+    }
+
+    #[test]
+    fn hit_from_inside() {
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(ellipsoid().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 2.0);
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn spans() {
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let spans = ellipsoid().hit_intervals(&r);
+        assert_eq!(spans.len(), 1);
+        assert_close(spans[0].enter.t, 3.0);
+        assert_close(spans[0].exit.t, 7.0);
+
+        let miss = Ray::new(Point3::new(-5.0, 1.5, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(ellipsoid().hit_intervals(&miss).is_empty());
+        assert!(!ellipsoid().hit(&miss, 0.001, common::INFINITY, &mut HitRecord::new()));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut output_box = Aabb::default();
+        assert!(ellipsoid().bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-2.0, -1.0, -1.0), Vec3::new(2.0, 1.0, 1.0));
+    }
+}
@@ -0,0 +1,82 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use crate::film::Region;
+
+#[derive(Debug)]
+pub enum Error {
+    // File that could not be read or written
+    Io { path: String, source: io::Error },
+    // File read but not in the expected format
+    InvalidFile { path: String, message: String },
+    UnknownScene(String),
+    // Scene without bounded objects to frame the camera on
+    NothingToFrame(String),
+    // A ray hit an object without a material to shade it with
+    MissingMaterial,
+    // Region which is empty or not inside the image
+    InvalidRegion { region: Region, width: usize, height: usize },
+    // Checkpoint saved by a render differing from the one resuming from it in `setting`
+    CheckpointMismatch { path: String, setting: &'static str },
+    // Render stopped by its cancel token
+    Cancelled,
+    // Images compared with a reference of another size
+    SizeMismatch { size: (usize, usize), reference_size: (usize, usize) },
+    // Images without pixels compared
+    EmptyImage,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(path: &str, source: io::Error) -> Error {
+        Error::Io {
+            path: path.to_string(),
+            source,
+        }
+    }
+
+    pub fn invalid_file(path: &str, message: &str) -> Error {
+        Error::InvalidFile {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::InvalidFile { path, message } => write!(f, "{}: {}", path, message),
+            Error::UnknownScene(name) => write!(f, "unknown scene '{}'", name),
+            Error::NothingToFrame(name) => write!(f, "nothing to frame in '{}'", name),
+            Error::MissingMaterial => write!(f, "a ray hit an object without material"),
+            Error::InvalidRegion { region, width, height } => write!(
+                f,
+                "region {},{},{},{} is empty or outside of the {}x{} image",
+                region.x0, region.y0, region.x1, region.y1, width, height
+            ),
+            Error::CheckpointMismatch { path, setting } => {
+                write!(f, "{}: checkpoint of a render with another {}", path, setting)
+            }
+            Error::Cancelled => write!(f, "render cancelled"),
+            Error::SizeMismatch { size, reference_size } => write!(
+                f,
+                "image of {}x{} pixels compared with a reference of {}x{}",
+                size.0, size.1, reference_size.0, reference_size.1
+            ),
+            Error::EmptyImage => write!(f, "cannot compare images without pixels"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::fs;
+use std::io::{self, Read, Write};
+
+use crate::color::{self, Color};
+use crate::common::RngState;
+use crate::error::{Error, Result};
+use crate::image::{self, Image};
+use crate::texture;
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"RTCKPT03";
+
+// Rectangle of pixels [x0, x1) x [y0, y1) of the image, rows counted from the top
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Region {
+    pub fn width(&self) -> usize {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> usize {
+        self.y1 - self.y0
+    }
+
+    // Whether the region is not empty and fits in a `width` x `height` image
+    fn is_valid(&self, width: usize, height: usize) -> bool {
+        self.x0 < self.x1 && self.y0 < self.y1 && self.x1 <= width && self.y1 <= height
+    }
+
+    fn contains(&self, i: usize, row: usize) -> bool {
+        i >= self.x0 && i < self.x1 && row >= self.y0 && row < self.y1
+    }
+}
+
+// What a render resuming from a checkpoint must have in common with the one
+// which saved it, besides the image size and region: a fingerprint of the
+// scene, camera and render settings, and the samples per pixel aimed at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CheckpointKey {
+    pub fingerprint: u64,
+    pub samples_per_pixel: u32,
+}
+
+impl CheckpointKey {
+    // Key of the render described by `description`, which should name everything
+    // the image depends on. It is hashed with FNV-1a, which unlike the standard
+    // library hasher gives the same fingerprint in every build.
+    pub fn new(description: &str, samples_per_pixel: u32) -> CheckpointKey {
+        let fingerprint = description
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash: u64, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+        CheckpointKey {
+            fingerprint,
+            samples_per_pixel,
+        }
+    }
+}
+
+// Accumulation buffer: the sum of the samples taken so far in every pixel of
+// the rendered region (the whole image by default). Samples are added in
+// passes over the region, so every pixel holds the same number of samples and
+// the image can be written at any time.
+pub struct Film {
+    width: usize,
+    height: usize,
+    region: Region,
+    pixels: Vec<Color>,
+    passes: u32,
+}
+
+impl Film {
+    // Film of a `width` x `height` image of which only `region` is rendered
+    pub fn with_region(width: usize, height: usize, region: Region) -> Result<Film> {
+        if !region.is_valid(width, height) {
+            return Err(Error::InvalidRegion { region, width, height });
+        }
+        Ok(Film {
+            width,
+            height,
+            region,
+            pixels: vec![Color::default(); region.width() * region.height()],
+            passes: 0,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    // Add a pass: one sample for every pixel of the region, row by row from the top
+    pub fn add_pass(&mut self, samples: &[Color]) {
+        for (pixel, sample) in self.pixels.iter_mut().zip(samples) {
+            *pixel += *sample;
+        }
+        self.passes += 1;
+    }
+
+    // Current estimate of the region: the average of the samples of every pixel
+    pub fn image(&self) -> Image {
+        let scale = 1.0 / self.passes.max(1) as f64;
+        let pixels = self.pixels.iter().map(|pixel| scale * *pixel).collect();
+        Image::new(self.region.width(), self.region.height(), pixels)
+    }
+
+    // Write the current estimate of the region as an ASCII PPM
+    pub fn write_ppm(&self, file_name: &str) -> Result<()> {
+        self.image().write_ppm(file_name)
+    }
+
+    // Write the full-size image with the region pasted over the image already
+    // in `file_name` (a previous full render), or over black if there is no
+    // image of the same size there yet
+    pub fn write_composite_ppm(&self, file_name: &str) -> Result<()> {
+        let background = match texture::read_ppm(file_name) {
+            Ok((width, height, data)) if width == self.width && height == self.height => data,
+            _ => vec![Color::default(); self.width * self.height],
+        };
+
+        image::write_file(file_name, |writer| {
+            writeln!(writer, "P3\n{} {}\n255", self.width, self.height)?;
+            for row in 0..self.height {
+                for i in 0..self.width {
+                    if self.region.contains(i, row) {
+                        let index = (row - self.region.y0) * self.region.width() + (i - self.region.x0);
+                        color::write_color(writer, self.pixels[index], self.passes.max(1) as i32)?;
+                    } else {
+                        // Already gamma corrected 8-bit values
+                        let c = 255.0 * background[row * self.width + i];
+                        writeln!(writer, "{} {} {}", c.x().round(), c.y().round(), c.z().round())?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // Save everything needed to continue the render later: the accumulated
+    // samples, the number of passes, the key of the render and the state of the
+    // random generator. Binary little endian layout: magic, width, height,
+    // region (x0, y0, x1, y1), passes, samples per pixel and fingerprint of the
+    // key, RNG seed, stream and word position, then the RGB sums of every pixel
+    // of the region.
+    pub fn save_checkpoint(&self, file_name: &str, key: &CheckpointKey, rng: &RngState) -> Result<()> {
+        image::write_file(file_name, |writer| {
+            writer.write_all(CHECKPOINT_MAGIC)?;
+            writer.write_all(&(self.width as u64).to_le_bytes())?;
+            writer.write_all(&(self.height as u64).to_le_bytes())?;
+            for bound in [self.region.x0, self.region.y0, self.region.x1, self.region.y1] {
+                writer.write_all(&(bound as u64).to_le_bytes())?;
+            }
+            writer.write_all(&self.passes.to_le_bytes())?;
+            writer.write_all(&key.samples_per_pixel.to_le_bytes())?;
+            writer.write_all(&key.fingerprint.to_le_bytes())?;
+            writer.write_all(&rng.seed)?;
+            writer.write_all(&rng.stream.to_le_bytes())?;
+            writer.write_all(&rng.word_pos.to_le_bytes())?;
+            for pixel in &self.pixels {
+                for c in [pixel.x(), pixel.y(), pixel.z()] {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    pub fn load_checkpoint(file_name: &str) -> Result<(Film, CheckpointKey, RngState)> {
+        let bytes = fs::read(file_name).map_err(|e| Error::io(file_name, e))?;
+        let mut reader = &bytes[..];
+        let invalid = |msg: &str| Error::invalid_file(file_name, msg);
+        let truncated = |_: io::Error| invalid("truncated checkpoint");
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(truncated)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(invalid("not a checkpoint"));
+        }
+
+        let mut u32_bytes = [0u8; 4];
+        let mut u64_bytes = [0u8; 8];
+        let mut u128_bytes = [0u8; 16];
+        reader.read_exact(&mut u64_bytes).map_err(truncated)?;
+        let width = u64::from_le_bytes(u64_bytes) as usize;
+        reader.read_exact(&mut u64_bytes).map_err(truncated)?;
+        let height = u64::from_le_bytes(u64_bytes) as usize;
+        let mut bounds = [0; 4];
+        for bound in &mut bounds {
+            reader.read_exact(&mut u64_bytes).map_err(truncated)?;
+            *bound = u64::from_le_bytes(u64_bytes) as usize;
+        }
+        let region = Region {
+            x0: bounds[0],
+            y0: bounds[1],
+            x1: bounds[2],
+            y1: bounds[3],
+        };
+        if !region.is_valid(width, height) {
+            return Err(invalid("bad region"));
+        }
+        reader.read_exact(&mut u32_bytes).map_err(truncated)?;
+        let passes = u32::from_le_bytes(u32_bytes);
+        reader.read_exact(&mut u32_bytes).map_err(truncated)?;
+        let samples_per_pixel = u32::from_le_bytes(u32_bytes);
+        reader.read_exact(&mut u64_bytes).map_err(truncated)?;
+        let fingerprint = u64::from_le_bytes(u64_bytes);
+
+        let mut seed = [0u8; 32];
+        reader.read_exact(&mut seed).map_err(truncated)?;
+        reader.read_exact(&mut u64_bytes).map_err(truncated)?;
+        let stream = u64::from_le_bytes(u64_bytes);
+        reader.read_exact(&mut u128_bytes).map_err(truncated)?;
+        let word_pos = u128::from_le_bytes(u128_bytes);
+
+        // Three f64 sums per pixel, which must all be in the file before
+        // anything is allocated for them
+        let count = region.width().checked_mul(region.height()).filter(|&n| n <= reader.len() / 24);
+        let count = count.ok_or_else(|| invalid("truncated checkpoint"))?;
+        let mut pixels = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut rgb = [0.0; 3];
+            for c in &mut rgb {
+                reader.read_exact(&mut u64_bytes).map_err(truncated)?;
+                *c = f64::from_le_bytes(u64_bytes);
+            }
+            pixels.push(Color::new(rgb[0], rgb[1], rgb[2]));
+        }
+
+        let film = Film {
+            width,
+            height,
+            region,
+            pixels,
+            passes,
+        };
+        let key = CheckpointKey {
+            fingerprint,
+            samples_per_pixel,
+        };
+        Ok((film, key, RngState { seed, stream, word_pos }))
+    }
+
+    // Load the checkpoint of a `width` x `height` render of `region` with `key`,
+    // refusing one saved by any other render
+    pub fn resume(
+        file_name: &str,
+        width: usize,
+        height: usize,
+        region: Region,
+        key: &CheckpointKey,
+    ) -> Result<(Film, RngState)> {
+        let (film, saved_key, rng) = Film::load_checkpoint(file_name)?;
+        let mismatch = |setting| Error::CheckpointMismatch {
+            path: file_name.to_string(),
+            setting,
+        };
+        if film.width != width || film.height != height || film.region != region {
+            return Err(mismatch("image size or region"));
+        }
+        if saved_key.fingerprint != key.fingerprint {
+            return Err(mismatch("scene, camera or render settings"));
+        }
+        if saved_key.samples_per_pixel != key.samples_per_pixel {
+            return Err(mismatch("number of samples per pixel"));
+        }
+        Ok((film, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::common;
+    use crate::testing::assert_vec_close;
+
+    fn temp_file(name: &str) -> String {
+        let path = env::temp_dir().join(format!("rt-{}-{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn region() -> Region {
+        Region { x0: 1, y0: 2, x1: 4, y1: 4 }
+    }
+
+    #[test]
+    fn reject_regions_outside_of_the_image() {
+        let outside = Region { x0: 2, y0: 0, x1: 9, y1: 4 };
+        assert!(matches!(Film::with_region(8, 4, outside), Err(Error::InvalidRegion { .. })));
+        let empty = Region { x0: 2, y0: 2, x1: 2, y1: 4 };
+        assert!(matches!(Film::with_region(8, 4, empty), Err(Error::InvalidRegion { .. })));
+    }
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let mut film = Film::with_region(8, 4, region()).unwrap();
+        let samples: Vec<Color> = (0..6).map(|i| Color::new(i as f64, 0.5, 1.0)).collect();
+        film.add_pass(&samples);
+        film.add_pass(&samples);
+        common::seed_rng(7);
+        let rng = common::rng_state();
+
+        let path = temp_file("round-trip.checkpoint");
+        let key = CheckpointKey::new("scene", 16);
+        film.save_checkpoint(&path, &key, &rng).unwrap();
+        let loaded = Film::load_checkpoint(&path);
+        fs::remove_file(&path).unwrap();
+
+        let (loaded, loaded_key, loaded_rng) = loaded.unwrap();
+        assert_eq!(loaded_key, key);
+        assert_eq!((loaded.width(), loaded.height(), loaded.region()), (8, 4, region()));
+        assert_eq!(loaded.passes(), 2);
+        assert_eq!((loaded_rng.seed, loaded_rng.stream, loaded_rng.word_pos), (rng.seed, rng.stream, rng.word_pos));
+        assert_vec_close(loaded.image().pixel(2, 1), Color::new(5.0, 0.5, 1.0));
+    }
+
+    #[test]
+    fn reject_truncated_and_oversized_checkpoints() {
+        let film = Film::with_region(8, 4, region()).unwrap();
+        let path = temp_file("corrupt.checkpoint");
+        film.save_checkpoint(&path, &CheckpointKey::new("scene", 16), &common::rng_state()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        // Last pixel cut off
+        fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+        let truncated = Film::load_checkpoint(&path);
+
+        // Huge image and region in a header of the right layout
+        let mut huge = bytes.clone();
+        let size = (u64::MAX / 2).to_le_bytes();
+        for offset in [8, 16, 40, 48] {
+            huge[offset..offset + 8].copy_from_slice(&size);
+        }
+        fs::write(&path, &huge).unwrap();
+        let oversized = Film::load_checkpoint(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(truncated, Err(Error::InvalidFile { .. })));
+        assert!(matches!(oversized, Err(Error::InvalidFile { .. })));
+    }
+    #[test]
+    fn resume_only_the_same_render() {
+        let film = Film::with_region(8, 4, region()).unwrap();
+        let key = CheckpointKey::new("fog, perspective camera", 16);
+        let path = temp_file("resume.checkpoint");
+        film.save_checkpoint(&path, &key, &common::rng_state()).unwrap();
+
+        let same = Film::resume(&path, 8, 4, region(), &key);
+        let other_region = Film::resume(&path, 8, 4, Region { x0: 0, y0: 0, x1: 8, y1: 4 }, &key);
+        let other_scene = Film::resume(&path, 8, 4, region(), &CheckpointKey::new("sdf, perspective camera", 16));
+        let other_samples = Film::resume(&path, 8, 4, region(), &CheckpointKey::new("fog, perspective camera", 64));
+        fs::remove_file(&path).unwrap();
+
+        assert!(same.is_ok());
+        for (resumed, expected) in [
+            (other_region, "image size or region"),
+            (other_scene, "scene, camera or render settings"),
+            (other_samples, "number of samples per pixel"),
+        ] {
+            assert!(matches!(resumed, Err(Error::CheckpointMismatch { setting, .. }) if setting == expected));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::rc::Rc;
  
+use crate::aabb::Aabb;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::{self, Point3, Vec3};
@@ -10,6 +11,10 @@ pub struct HitRecord {
     pub normal: Vec3,
     pub mat: Option<Rc<dyn Material>>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
     pub front_face: bool,
 }
  
@@ -26,8 +31,72 @@ impl HitRecord {
             -outward_normal
         };
     }
+
+    // Build an orthonormal tangent frame around the (already face-oriented) normal.
+    // `dpdu` is the surface derivative along u; if it is degenerate an arbitrary
+    // tangent is picked so that normal/bump maps still have a frame to work in.
+    // The bitangent is taken around the outward normal, so that it keeps following
+    // v and normal maps are not mirrored on back faces.
+    pub fn set_tangent_frame(&mut self, dpdu: Vec3) {
+        let mut tangent = dpdu - vec3::dot(dpdu, self.normal) * self.normal;
+        if tangent.near_zero() {
+            let helper = if self.normal.x().abs() > 0.9 {
+                Vec3::new(0.0, 1.0, 0.0)
+            } else {
+                Vec3::new(1.0, 0.0, 0.0)
+            };
+            tangent = vec3::cross(helper, self.normal);
+        }
+        self.tangent = vec3::unit_vector(tangent);
+        let outward_normal = if self.front_face { self.normal } else { -self.normal };
+        self.bitangent = vec3::cross(outward_normal, self.tangent);
+    }
+}
+ 
+// Portion of a ray inside a closed object, from where it enters to where it exits
+#[derive(Clone, Default)]
+pub struct Span {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
 }
  
 pub trait Hittable {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
-}
\ No newline at end of file
+ 
+    // Box enclosing the object; returns false for unbounded objects
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool;
+ 
+    // Every span of the whole ray line (t from -infinity to +infinity) lying inside
+    // the object, sorted by t. Only closed objects can take part in CSG operations;
+    // the others report no spans.
+    fn hit_intervals(&self, _ray: &Ray) -> Vec<Span> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_vec_close;
+
+    fn frame_seen_from(origin: Point3) -> HitRecord {
+        let r = Ray::new(origin, -origin, 0.0);
+        let mut rec = HitRecord::new();
+        rec.set_face_normal(&r, Vec3::new(0.0, 0.0, 1.0));
+        rec.set_tangent_frame(Vec3::new(1.0, 0.0, 0.0));
+        rec
+    }
+
+    #[test]
+    fn tangent_frame_follows_uv_on_both_faces() {
+        let front = frame_seen_from(Point3::new(0.0, 0.0, 1.0));
+        let back = frame_seen_from(Point3::new(0.0, 0.0, -1.0));
+        assert!(front.front_face);
+        assert!(!back.front_face);
+
+        for rec in [front, back] {
+            assert_vec_close(rec.tangent, Vec3::new(1.0, 0.0, 0.0));
+            assert_vec_close(rec.bitangent, Vec3::new(0.0, 1.0, 0.0));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::aabb::{self, Aabb};
 use crate::hittable::{HitRecord, Hittable};
 use crate::ray::Ray;
  
@@ -14,6 +15,22 @@ impl HittableList {
     pub fn add(&mut self, object: Box<dyn Hittable>) {
         self.objects.push(object);
     }
+ 
+    // Box around the objects that have one, leaving out unbounded objects
+    // such as infinite planes
+    pub fn bounded_objects_box(&self) -> Option<Aabb> {
+        let mut output_box: Option<Aabb> = None;
+        let mut temp_box = Aabb::default();
+        for object in &self.objects {
+            if object.bounding_box(&mut temp_box) {
+                output_box = Some(match output_box {
+                    Some(b) => aabb::surrounding_box(&b, &temp_box),
+                    None => temp_box,
+                });
+            }
+        }
+        output_box
+    }
 }
  
 impl Hittable for HittableList {
@@ -32,4 +49,26 @@ impl Hittable for HittableList {
  
         hit_anything
     }
+ 
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        if self.objects.is_empty() {
+            return false;
+        }
+ 
+        let mut temp_box = Aabb::default();
+        let mut first_box = true;
+        for object in &self.objects {
+            if !object.bounding_box(&mut temp_box) {
+                return false;
+            }
+            *output_box = if first_box {
+                temp_box
+            } else {
+                aabb::surrounding_box(output_box, &temp_box)
+            };
+            first_box = false;
+        }
+ 
+        true
+    }
 }
\ No newline at end of file
@@ -0,0 +1,190 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+use crate::color::{self, Color};
+use crate::error::{Error, Result};
+use crate::png;
+use crate::texture;
+
+// Rendered image in memory: linear RGB values, rows from the top
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Image {
+        Image { width, height, pixels }
+    }
+
+    // Load a PPM (8-bit), PFM (float) or PNG (8 or 16-bit) image, told apart
+    // by their first bytes. Like the images written by the renderer, PPM and
+    // PNG values are taken as gamma 2 and converted back to linear values.
+    pub fn load(path: &str) -> Result<Image> {
+        let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+        let from_gamma = |c: f64| c * c;
+        if bytes.starts_with(b"PF") || bytes.starts_with(b"Pf") {
+            read_pfm(path, &bytes)
+        } else if png::is_png(&bytes) {
+            let image = png::read_png(path)?;
+            let pixels = image
+                .samples
+                .chunks(3)
+                .map(|c| Color::new(from_gamma(c[0]), from_gamma(c[1]), from_gamma(c[2])))
+                .collect();
+            Ok(Image::new(image.width, image.height, pixels))
+        } else {
+            let (width, height, data) = texture::read_ppm(path)?;
+            let pixels = data
+                .iter()
+                .map(|c| Color::new(from_gamma(c.x()), from_gamma(c.y()), from_gamma(c.z())))
+                .collect();
+            Ok(Image::new(width, height, pixels))
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    // Gamma corrected 8-bit RGB values, row by row from the top
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|pixel| color::to_rgb8(*pixel, 1)).collect()
+    }
+
+    // Write the image as a gamma corrected 8-bit PNG
+    pub fn write_png(&self, file_name: &str) -> Result<()> {
+        png::write_png(file_name, self.width, self.height, &self.to_rgb8())
+    }
+
+    // Write the image as a gamma corrected ASCII PPM
+    pub fn write_ppm(&self, file_name: &str) -> Result<()> {
+        write_file(file_name, |writer| {
+            writeln!(writer, "P3\n{} {}\n255", self.width, self.height)?;
+            for pixel in &self.pixels {
+                color::write_color(writer, *pixel, 1)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+// Portable float map: "PF" (RGB) or "Pf" (gray), the size, then a scale whose
+// sign gives the byte order (negative for little endian) and the 32-bit float
+// values, row by row from the bottom
+fn read_pfm(path: &str, bytes: &[u8]) -> Result<Image> {
+    let invalid = |msg: &str| Error::invalid_file(path, msg);
+
+    // Three header lines
+    let mut lines = Vec::new();
+    let mut pos = 0;
+    while lines.len() < 3 {
+        let end = bytes[pos..].iter().position(|&b| b == b'\n').ok_or_else(|| invalid("truncated header"))?;
+        lines.push(String::from_utf8_lossy(&bytes[pos..pos + end]).trim().to_string());
+        pos += end + 1;
+    }
+    let channels = if lines[0] == "PF" { 3 } else { 1 };
+    let size: Vec<usize> = lines[1].split_whitespace().filter_map(|s| s.parse().ok()).collect();
+    let [width, height] = size[..] else {
+        return Err(invalid("bad size"));
+    };
+    let scale: f64 = lines[2].parse().map_err(|_| invalid("bad scale"))?;
+
+    let count = width.checked_mul(height).and_then(|n| n.checked_mul(channels));
+    let data = count
+        .and_then(|count| bytes.get(pos..pos.checked_add(count.checked_mul(4)?)?))
+        .ok_or_else(|| invalid("truncated pixel data"))?;
+    let values: Vec<f64> = data
+        .chunks(4)
+        .map(|b| {
+            let b = [b[0], b[1], b[2], b[3]];
+            (if scale < 0.0 { f32::from_le_bytes(b) } else { f32::from_be_bytes(b) }) as f64
+        })
+        .collect();
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in (0..height).rev() {
+        for value in values[row * width * channels..(row + 1) * width * channels].chunks(channels) {
+            pixels.push(match value {
+                [r, g, b] => Color::new(*r, *g, *b),
+                _ => Color::new(value[0], value[0], value[0]),
+            });
+        }
+    }
+    Ok(Image::new(width, height, pixels))
+}
+
+// Write a file through `write`. The file is written to a temporary file first
+// and then renamed, so a viewer never sees it half done and a crash never
+// leaves it truncated.
+pub(crate) fn write_file(file_name: &str, write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>) -> Result<()> {
+    let temp_name = format!("{}.tmp", file_name);
+    let result = File::create(&temp_name).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()
+    });
+    result
+        .and_then(|_| fs::rename(&temp_name, file_name))
+        .map_err(|e| Error::io(file_name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::testing::assert_vec_close;
+
+    fn load_bytes(name: &str, bytes: &[u8]) -> Result<Image> {
+        let path = env::temp_dir().join(format!("rt-{}-{}", std::process::id(), name));
+        let path = path.to_str().unwrap();
+        fs::write(path, bytes).unwrap();
+        let image = Image::load(path);
+        fs::remove_file(path).unwrap();
+        image
+    }
+
+    #[test]
+    fn load_pfm_bottom_row_first() {
+        // Little endian 2 x 2 gray image, rows from the bottom
+        let mut bytes = b"Pf\n2 2\n-1.0\n".to_vec();
+        for value in [0.25f32, 0.5, 1.0, 2.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let image = load_bytes("gray.pfm", &bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 2));
+        assert_vec_close(image.pixel(0, 0), Color::new(1.0, 1.0, 1.0));
+        assert_vec_close(image.pixel(1, 1), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn load_ppm_back_to_linear_values() {
+        let image = load_bytes("gray.ppm", b"P3\n1 1\n255\n0 51 255\n").unwrap();
+        assert_vec_close(image.pixel(0, 0), Color::new(0.0, 0.04, 1.0));
+    }
+
+    #[test]
+    fn reject_oversized_headers() {
+        let size = u64::MAX / 2;
+        let pfm = format!("PF\n{} {}\n-1.0\n", size, size);
+        assert!(matches!(load_bytes("huge.pfm", pfm.as_bytes()), Err(Error::InvalidFile { .. })));
+        let ppm = format!("P3\n{} {}\n255\n0 0 0\n", size, size);
+        assert!(matches!(load_bytes("huge.ppm", ppm.as_bytes()), Err(Error::InvalidFile { .. })));
+        let ppm = format!("P6\n{} 2\n255\n", usize::MAX / 4);
+        assert!(matches!(load_bytes("long.ppm", ppm.as_bytes()), Err(Error::InvalidFile { .. })));
+    }
+}
@@ -0,0 +1,313 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::animation::{CameraKey, CameraPath, Interpolation, PathPreset};
+use crate::camera::{self, Camera, CameraSettings};
+use crate::common;
+use crate::error::{Error, Result};
+use crate::film::{CheckpointKey, Film, Region};
+use crate::progress::Progress;
+use crate::renderer::{DebugMode, Renderer};
+use crate::scene::Scene;
+use crate::scenes;
+use crate::vec3::{Point3, Vec3};
+
+// Size of the image of a single view, whose height follows from the aspect
+// ratio of the projection
+const IMAGE_WIDTH: usize = 400;
+const ASPECT_RATIO: f64 = 16.0 / 9.0;
+const MAX_DEPTH: i32 = 50;
+
+// Debug mode of the images, given the key of the still camera
+pub type DebugModeCallback = Box<dyn Fn(&CameraKey) -> DebugMode>;
+
+// Function called with the messages of a job
+pub type MessageCallback = Box<dyn Fn(&str)>;
+
+// Progress callback shared by the renderers of the scenes of a job
+type SharedProgressCallback = Rc<dyn Fn(&Progress)>;
+
+// Render of a built-in scene to image files, as the command line does. The
+// camera looks at the scene from above, or frames its objects, and may move
+// along a preset path to render one image per frame of an animation. Each
+// image is rendered in passes: previews of it can be written as the render
+// goes, and its state saved next to it (image.checkpoint) for a later render
+// to resume from. Only a render of the same scene, camera and settings can
+// resume a checkpoint.
+pub struct RenderJob {
+    camera: CameraSettings,
+    samples_per_pixel: u32,
+    region: Option<Region>,
+    debug_mode: Option<DebugModeCallback>,
+    // Margin around the framed objects and direction to look at them from
+    auto_frame: Option<(f64, Option<Vec3>)>,
+    // Path of the camera, how it goes through its keys and number of frames
+    animation: Option<(PathPreset, Interpolation, usize)>,
+    preview_interval: Option<Duration>,
+    checkpoint_interval: Option<Duration>,
+    resume: bool,
+    composite: bool,
+    stats_json: bool,
+    progress: Option<SharedProgressCallback>,
+    messages: Option<MessageCallback>,
+}
+
+impl RenderJob {
+    pub fn new(camera: CameraSettings) -> RenderJob {
+        RenderJob {
+            camera,
+            samples_per_pixel: 100,
+            region: None,
+            debug_mode: None,
+            auto_frame: None,
+            animation: None,
+            preview_interval: None,
+            checkpoint_interval: None,
+            resume: false,
+            composite: false,
+            stats_json: false,
+            progress: None,
+            messages: None,
+        }
+    }
+
+    pub fn with_samples(mut self, samples_per_pixel: u32) -> RenderJob {
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+
+    // Render only `region` of the images
+    pub fn with_region(mut self, region: Region) -> RenderJob {
+        self.region = Some(region);
+        self
+    }
+
+    // Render the debug mode that `mode` gives for the still camera, e.g. a
+    // depth scaled by its focus distance
+    pub fn with_debug_mode(mut self, mode: impl Fn(&CameraKey) -> DebugMode + 'static) -> RenderJob {
+        self.debug_mode = Some(Box::new(mode));
+        self
+    }
+
+    // Frame the objects of the scene, without its infinite ground, leaving
+    // `margin` around them and looking along `direction` (the default view
+    // direction if None)
+    pub fn with_auto_frame(mut self, margin: f64, direction: Option<Vec3>) -> RenderJob {
+        self.auto_frame = Some((margin, direction));
+        self
+    }
+
+    // Render `frames` images along the path of `preset`, starting from the
+    // still camera
+    pub fn with_animation(mut self, preset: PathPreset, interpolation: Interpolation, frames: usize) -> RenderJob {
+        self.animation = Some((preset, interpolation, frames));
+        self
+    }
+
+    // Write the image rendered so far every `interval`
+    pub fn with_previews(mut self, interval: Duration) -> RenderJob {
+        self.preview_interval = Some(interval);
+        self
+    }
+
+    // Save the state of the render every `interval` and after the last pass
+    pub fn with_checkpoints(mut self, interval: Duration) -> RenderJob {
+        self.checkpoint_interval = Some(interval);
+        self
+    }
+
+    // Resume each image from its checkpoint, up to the samples per pixel of the job
+    pub fn with_resume(mut self) -> RenderJob {
+        self.resume = true;
+        self
+    }
+
+    // Paste the rendered region into the whole image instead of writing it alone
+    pub fn with_composite(mut self) -> RenderJob {
+        self.composite = true;
+        self
+    }
+
+    // Write the statistics of each image as JSON next to it (image.stats.json)
+    pub fn with_stats_json(mut self) -> RenderJob {
+        self.stats_json = true;
+        self
+    }
+
+    // Call `callback` as each image renders, after every row of every pass
+    pub fn with_progress(mut self, callback: impl Fn(&Progress) + 'static) -> RenderJob {
+        self.progress = Some(Rc::new(callback));
+        self
+    }
+
+    // Call `callback` with the messages of the job: resumed renders, previews
+    // written, images done and their statistics
+    pub fn with_messages(mut self, callback: impl Fn(&str) + 'static) -> RenderJob {
+        self.messages = Some(Box::new(callback));
+        self
+    }
+
+    // Render the scene `name` to `file_name`, or for an animation to a
+    // sequence numbered from 1: image/scene_0001.ppm, ...
+    pub fn render(&self, name: &str, file_name: &str) -> Result<()> {
+        let scene_start = Instant::now();
+        let scene = scenes::create_scene(name)?;
+        let scene_time = scene_start.elapsed();
+
+        let key = self.still_camera(name, &scene)?;
+        let (_, aspect_ratio) = self.camera.camera(&key, ASPECT_RATIO)?;
+        let mut renderer = Renderer::new(IMAGE_WIDTH, ((IMAGE_WIDTH as f64) / aspect_ratio) as usize)
+            .with_samples(self.samples_per_pixel)
+            .with_max_depth(MAX_DEPTH);
+        if let Some(region) = self.region {
+            renderer = renderer.with_region(region);
+        }
+        let debug_mode = self.debug_mode.as_ref().map(|mode| mode(&key));
+        if let Some(mode) = debug_mode {
+            renderer = renderer.with_debug_mode(mode);
+        }
+        if let Some(progress) = &self.progress {
+            let progress = Rc::clone(progress);
+            renderer = renderer.with_progress(move |p| progress(p));
+        }
+
+        let image = |key: &CameraKey, file_name: &str| {
+            let (camera, _) = self.camera.camera(key, ASPECT_RATIO)?;
+            let checkpoint_key = CheckpointKey::new(&self.description(name, key, debug_mode), self.samples_per_pixel);
+            self.render_image(&renderer, &scene, camera.as_ref(), &checkpoint_key, scene_time, file_name)
+        };
+        let Some((preset, interpolation, frames)) = self.animation else {
+            return image(&key, file_name);
+        };
+        let path = CameraPath::preset(preset, interpolation, &key);
+        let stem = file_name.trim_end_matches(".ppm");
+        for (frame, time) in path.frame_times(frames).into_iter().enumerate() {
+            image(&path.at(time).unwrap(), &format!("{}_{:04}.ppm", stem, frame + 1))?;
+        }
+        Ok(())
+    }
+
+    // Camera looking at the origin from above, or framing the objects of the scene
+    fn still_camera(&self, name: &str, scene: &Scene) -> Result<CameraKey> {
+        let lookfrom = Point3::new(6.0, 12.0, -20.0);
+        let lookat = Point3::new(0.0, 1.0, 0.0);
+        let key = CameraKey {
+            time: 0.0,
+            lookfrom,
+            lookat,
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: self.camera.perspective_fov(),
+            focus_distance: (lookat - lookfrom).length(),
+        };
+        let Some((margin, direction)) = self.auto_frame else {
+            return Ok(key);
+        };
+
+        let bounds = scene
+            .objects()
+            .bounded_objects_box()
+            .ok_or_else(|| Error::NothingToFrame(name.to_string()))?;
+        let direction = direction.unwrap_or(lookat - lookfrom);
+        let (lookfrom, lookat) = camera::frame_box(&bounds, direction, key.vup, key.vfov, ASPECT_RATIO, margin);
+        Ok(CameraKey {
+            lookfrom,
+            lookat,
+            focus_distance: (lookat - lookfrom).length(),
+            ..key
+        })
+    }
+
+    // Everything an image depends on besides its size and region: the scene,
+    // the camera and the render settings. Checkpoints keep its fingerprint.
+    fn description(&self, name: &str, key: &CameraKey, debug_mode: Option<DebugMode>) -> String {
+        format!(
+            "{} | {} {} {} {} {} {} | {:?} | {} {:?}",
+            name,
+            key.time,
+            key.lookfrom,
+            key.lookat,
+            key.vup,
+            key.vfov,
+            key.focus_distance,
+            self.camera,
+            MAX_DEPTH,
+            debug_mode
+        )
+    }
+
+    // Render one image in passes, writing previews and checkpoints in between,
+    // then the image and its statistics
+    fn render_image(
+        &self,
+        renderer: &Renderer,
+        scene: &Scene,
+        camera: &dyn Camera,
+        key: &CheckpointKey,
+        scene_time: Duration,
+        file_name: &str,
+    ) -> Result<()> {
+        let width = renderer.width();
+        let height = renderer.height();
+        let region = renderer.region();
+
+        let checkpoint_name = format!("{}.checkpoint", file_name.trim_end_matches(".ppm"));
+        let mut film = if self.resume {
+            let (film, rng) = Film::resume(&checkpoint_name, width, height, region, key)?;
+            common::set_rng_state(&rng);
+            self.message(&format!("Resuming {} after {} samples per pixel", file_name, film.passes()));
+            film
+        } else {
+            Film::with_region(width, height, region)?
+        };
+        let write_image = |film: &Film| {
+            if self.composite {
+                film.write_composite_ppm(file_name)
+            } else {
+                film.write_ppm(file_name)
+            }
+        };
+
+        let mut last_preview = Instant::now();
+        let mut last_checkpoint = Instant::now();
+        let mut write_time = Duration::ZERO;
+        let mut stats = renderer.render_film(scene, camera, &mut film, |film| {
+            let write_start = Instant::now();
+            let last_pass = film.passes() == self.samples_per_pixel;
+            if let Some(interval) = self.preview_interval {
+                if last_preview.elapsed() >= interval && !last_pass {
+                    write_image(film)?;
+                    self.message(&format!("Preview after {} samples per pixel: {}", film.passes(), file_name));
+                    last_preview = Instant::now();
+                }
+            }
+            if let Some(interval) = self.checkpoint_interval {
+                if last_checkpoint.elapsed() >= interval || last_pass {
+                    film.save_checkpoint(&checkpoint_name, key, &common::rng_state())?;
+                    last_checkpoint = Instant::now();
+                }
+            }
+            write_time += write_start.elapsed();
+            Ok(())
+        })?;
+        let write_start = Instant::now();
+        write_image(&film)?;
+        write_time += write_start.elapsed();
+        self.message(&format!("Done rendering: {}", file_name));
+
+        // Statistics by phase: building the scene, rendering and writing the
+        // images and checkpoints
+        stats.phases.insert(0, ("scene", scene_time));
+        stats.phases.push(("write", write_time));
+        self.message(&stats.to_string());
+        if self.stats_json {
+            stats.write_json(&format!("{}.stats.json", file_name.trim_end_matches(".ppm")))?;
+        }
+        Ok(())
+    }
+
+    fn message(&self, message: &str) {
+        if let Some(callback) = &self.messages {
+            callback(message);
+        }
+    }
+}
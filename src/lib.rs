@@ -0,0 +1,60 @@
+// Path tracer library: primitives, materials and textures to build a Scene,
+// cameras to look at it and a Renderer producing an in-memory Image. The
+// built-in scenes of the command line are in `scenes`, and a RenderJob renders
+// them to files as the command line does.
+
+pub mod aabb;
+pub mod animation;
+pub mod aperture;
+pub mod camera;
+pub mod capsule;
+pub mod color;
+pub mod common;
+pub mod cone;
+pub mod constant_medium;
+pub mod csg;
+pub mod cube;
+pub mod cylindre;
+pub mod disk;
+pub mod ellipsoid;
+pub mod error;
+pub mod film;
+pub mod hittable;
+pub mod hittable_list;
+pub mod image;
+pub mod job;
+pub mod light;
+pub mod material;
+pub mod metrics;
+pub mod microfacet;
+pub mod moving_sphere;
+pub mod normal_map;
+pub mod plane;
+pub mod png;
+pub mod poly;
+pub mod principled;
+pub mod progress;
+pub mod ray;
+pub mod rectangle;
+pub mod renderer;
+pub mod scene;
+pub mod scenes;
+pub mod sdf;
+pub mod sphere;
+pub mod stats;
+pub mod texture;
+pub mod torus;
+pub mod transform;
+pub mod vec3;
+
+#[cfg(test)]
+mod testing;
+
+pub use camera::Camera;
+pub use error::{Error, Result};
+pub use image::Image;
+pub use job::RenderJob;
+pub use progress::{CancelToken, Progress};
+pub use renderer::Renderer;
+pub use scene::Scene;
+pub use stats::RenderStats;
@@ -1,258 +1,244 @@
-use std::fs::{self, File};
-use std::io::{BufWriter, Write};
-use std::rc::Rc;
+use std::fs;
 use std::env;
+use std::process;
+use std::time::Duration;
+
+use rt::animation::{CameraKey, Interpolation, PathPreset};
+use rt::camera::{CameraSettings, Eye, FisheyeMapping, Projection};
+use rt::error::Error;
+use rt::film::Region;
+use rt::progress::Progress;
+use rt::renderer::DebugMode;
+use rt::scenes::SCENES;
+use rt::vec3::Vec3;
+use rt::RenderJob;
+
+// Avancement du rendu sur une seule ligne : pourcentage, temps écoulé et
+// restant, débit de rayons
+fn print_progress(progress: &Progress) {
+    let minutes = |d: Duration| format!("{}:{:02}", d.as_secs() / 60, d.as_secs() % 60);
+    let eta = progress.eta().map_or("-:--".to_string(), minutes);
+    eprint!(
+        "\r{:5.1}%  elapsed {}  left {}  {:.2} Mrays/s ",
+        progress.percent(),
+        minutes(progress.elapsed),
+        eta,
+        progress.rays_per_second() / 1e6
+    );
+}
 
-mod camera;
-mod color;
-mod common;
-mod cube;
-mod cylindre;
-mod hittable;
-mod hittable_list;
-mod material;
-mod light;
-mod ray;
-mod rectangle;
-mod sphere;
-mod vec3;
-
-use camera::Camera;
-use color::Color;
-use cube::Cube;
-use cylindre::Cylinder;
-use hittable_list::HittableList;
-use material::{Dielectric, Lambertian, Metal};
-use light::Light;
-use ray::Ray;
-use rectangle::Rectangle;
-use sphere::Sphere;
-use vec3::{Point3, Vec3};
-
-fn ray_color(r: &Ray, world: &dyn hittable::Hittable, depth: i32, lights: &[Light]) -> Color {
-    if depth <= 0 {
-        return Color::new(0.0, 0.0, 0.0);
+// Fichier de l'image d'un objet
+fn image_name(object_name: &str) -> String {
+    match object_name {
+        "flat" => "image/flat-plane.ppm".to_string(),
+        _ => format!("image/{}.ppm", object_name),
     }
+}
 
-    let mut rec = hittable::HitRecord::new();
-    if world.hit(r, 0.001, common::INFINITY, &mut rec) {
-        let mut total_light = Color::new(0.0, 0.0, 0.0);
-
-        for light in lights {
-            let light_dir = light.direction_to_light(&rec.p);
-            let light_intensity = light.get_intensity(light_dir.length());
+const USAGE: &str = "Usage: cargo run <object_name> [--camera <projection>] [--hfov <degrees>] [--ortho-height <height>] [--ipd <distance>] [--convergence <distance>] \
+[--aperture <diameter>] [--focus-distance <distance>] [--blades <count>] [--blade-rotation <degrees>] \
+[--aperture-mask <ppm>] [--cat-eye <amount>] [--chromatic-aberration <amount>] \
+[--animation <turntable|flythrough>] [--frames <count>] [--interpolation <linear|catmull-rom>] \
+[--fov <degrees>] [--auto-frame <margin>] [--view-direction <x,y,z>] [--progressive <seconds>] \
+[--samples <count>] [--checkpoint <seconds>] [--resume] [--region <x0,y0,x1,y1>] [--composite] [--stats-json] \
+[--debug <normal|uv|depth|front-face|material|intersections>]";
+const OBJECT_NAMES: &str = "object_name must be one of: sphere, cube, cylinder, flat, scene, flat_and_cube, bumpy, metals, glass, principled, fog, csg, shapes, sdf, motion, bokeh, all";
+const PROJECTIONS: &str = "projection must be one of: perspective, orthographic, fisheye, fisheye-equisolid, equirectangular, cylindrical, left-eye, right-eye, stereo, ods";
+
+// Options passées sur la ligne de commande
+struct Options {
+    camera: CameraSettings,
+    animation: Option<PathPreset>,
+    frames: usize,
+    interpolation: Interpolation,
+    auto_frame: Option<f64>,
+    view_direction: Option<Vec3>,
+    preview_interval: Option<Duration>,
+    samples_per_pixel: u32,
+    checkpoint_interval: Option<Duration>,
+    resume: bool,
+    region: Option<Region>,
+    composite: bool,
+    stats_json: bool,
+    debug: Option<String>,
+}
 
-            // Vérification des ombres pour chaque lumière
-            let shadow_ray = Ray::new(rec.p, light_dir);
-            let in_shadow = world.hit(&shadow_ray, 0.001, common::INFINITY, &mut hittable::HitRecord::new());
-            if !in_shadow {
-                let dot = rec.normal.dot(&light_dir).max(0.0);
-                total_light += light.color * (dot * light_intensity);
+// Lecture des options qui suivent le nom de l'objet
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        camera: CameraSettings::default(),
+        animation: None,
+        frames: 24,
+        interpolation: Interpolation::CatmullRom,
+        auto_frame: None,
+        view_direction: None,
+        preview_interval: None,
+        samples_per_pixel: 100,
+        checkpoint_interval: None,
+        resume: false,
+        region: None,
+        composite: false,
+        stats_json: false,
+        debug: None,
+    };
+
+    let mut projection_name = "perspective";
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        // Options sans valeur
+        match option.as_str() {
+            "--resume" => {
+                options.resume = true;
+                continue;
+            }
+            "--composite" => {
+                options.composite = true;
+                continue;
+            }
+            "--stats-json" => {
+                options.stats_json = true;
+                continue;
             }
+            _ => {}
         }
 
-        let mut scattered = Ray::default();
-        let mut attenuation = Color::default();
-        if rec.mat.as_ref().unwrap().scatter(r, &rec, &mut attenuation, &mut scattered) {
-            return attenuation * ray_color(&scattered, world, depth - 1, lights) + total_light;
+        let value = args.next().ok_or_else(|| format!("Missing value for option '{}'", option))?;
+        let invalid = || format!("Invalid value '{}' for option '{}'", value, option);
+        let number = || value.parse::<f64>().ok().filter(|x| *x >= 0.0).ok_or_else(invalid);
+        let distance = || value.parse::<f64>().ok().filter(|x| *x > 0.0).ok_or_else(invalid);
+        let camera = &mut options.camera;
+        match option.as_str() {
+            "--camera" => {
+                projection_name = value;
+                camera.projection = match value.as_str() {
+                    "perspective" => Projection::Perspective,
+                    "orthographic" => Projection::Orthographic,
+                    "fisheye" => Projection::Fisheye(FisheyeMapping::Equidistant),
+                    "fisheye-equisolid" => Projection::Fisheye(FisheyeMapping::Equisolid),
+                    "equirectangular" => Projection::Equirectangular,
+                    "cylindrical" => Projection::Cylindrical,
+                    "left-eye" => Projection::Eye(Eye::Left),
+                    "right-eye" => Projection::Eye(Eye::Right),
+                    "stereo" => Projection::Stereo,
+                    "ods" => Projection::Ods,
+                    _ => return Err(format!("Unknown projection '{}'\n{}", value, PROJECTIONS)),
+                }
+            }
+            "--hfov" => camera.hfov = Some(value.parse().ok().filter(|x| *x > 0.0 && *x <= 360.0).ok_or_else(invalid)?),
+            "--ortho-height" => camera.ortho_height = distance()?,
+            "--ipd" => camera.ipd = distance()?,
+            "--convergence" => camera.convergence = Some(distance()?),
+            "--aperture" => camera.aperture = number()?,
+            "--focus-distance" => camera.focus_distance = Some(distance()?),
+            "--blades" => camera.blades = Some(value.parse().ok().filter(|n| *n >= 3).ok_or_else(invalid)?),
+            "--blade-rotation" => camera.blade_rotation = value.parse().map_err(|_| invalid())?,
+            "--aperture-mask" => camera.aperture_mask = Some(value.clone()),
+            "--cat-eye" => camera.cat_eye = number()?,
+            "--chromatic-aberration" => camera.chromatic_aberration = number()?,
+            "--fov" => camera.fov = Some(distance()?),
+            "--animation" => {
+                options.animation = match value.as_str() {
+                    "turntable" => Some(PathPreset::Turntable),
+                    "flythrough" => Some(PathPreset::Flythrough),
+                    _ => return Err(format!("Unknown animation '{}', must be one of: turntable, flythrough", value)),
+                }
+            }
+            "--frames" => options.frames = value.parse().ok().filter(|n| *n > 0).ok_or_else(invalid)?,
+            "--auto-frame" => options.auto_frame = Some(number()?),
+            "--region" => {
+                let bounds: Vec<usize> = value.split(',').filter_map(|c| c.trim().parse().ok()).collect();
+                match bounds[..] {
+                    [x0, y0, x1, y1] if x0 < x1 && y0 < y1 => options.region = Some(Region { x0, y0, x1, y1 }),
+                    _ => return Err(invalid()),
+                }
+            }
+            "--view-direction" => {
+                let xyz: Vec<f64> = value.split(',').filter_map(|c| c.trim().parse().ok()).collect();
+                match xyz[..] {
+                    [x, y, z] if x != 0.0 || y != 0.0 || z != 0.0 => options.view_direction = Some(Vec3::new(x, y, z)),
+                    _ => return Err(invalid()),
+                }
+            }
+            "--samples" => options.samples_per_pixel = value.parse().ok().filter(|n| *n > 0).ok_or_else(invalid)?,
+            "--checkpoint" => options.checkpoint_interval = Some(Duration::from_secs_f64(number()?)),
+            "--progressive" => options.preview_interval = Some(Duration::from_secs_f64(number()?)),
+            "--debug" => match value.as_str() {
+                "normal" | "uv" | "depth" | "front-face" | "material" | "intersections" => {
+                    options.debug = Some(value.clone())
+                }
+                _ => return Err(invalid()),
+            },
+            "--interpolation" => {
+                options.interpolation = match value.as_str() {
+                    "linear" => Interpolation::Linear,
+                    "catmull-rom" => Interpolation::CatmullRom,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("Unknown option '{}'", option)),
         }
-        return total_light;
     }
 
-    let unit_direction = vec3::unit_vector(r.direction());
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
-}
-
-
-fn save_scene_image(cam: &Camera, world: &HittableList, image_width: i32, image_height: i32, samples_per_pixel: i32, max_depth: i32, file_name: &str) {
-    let file = File::create(file_name).expect("Unable to create file");
-    let mut writer = BufWriter::new(file);
-
-    writeln!(writer, "P3\n{} {}\n255", image_width, image_height).unwrap();
-
-    // Create lights array here
-    let lights = [
-        Light::new(Point3::new(-10.0, 10.0, -10.0), 0.2, Vec3::new(1.0, 1.0, 1.0)),
-        // Add more lights if needed
-    ];
-
-    for j in (0..image_height).rev() {
-        eprint!("\rScanlines remaining: {} ", j);
-        for i in 0..image_width {
-            let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-            for _ in 0..samples_per_pixel {
-                let u = (i as f64 + common::random_double()) / (image_width - 1) as f64;
-                let v = (j as f64 + common::random_double()) / (image_height - 1) as f64;
-                let r = cam.get_ray(u, v);
-                // Pass the lights array as reference
-                pixel_color += ray_color(&r, world, max_depth, &lights);
-            }
-            color::write_color(&mut writer, pixel_color, samples_per_pixel);
+    // Les fisheyes voient jusqu'à 360°, les autres projections moins de 180° en hauteur
+    if let Some(fov) = options.camera.fov {
+        let valid = match options.camera.projection {
+            Projection::Fisheye(_) => fov <= 360.0,
+            _ => fov < 180.0,
+        };
+        if !valid {
+            return Err(format!("Invalid value '{}' for option '--fov' with the {} projection", fov, projection_name));
         }
     }
-    eprintln!("\nDone rendering: {}", file_name);
+    Ok(options)
 }
 
-
-// Fonctions de création des scènes
-fn create_ground_scene() -> HittableList {
-    let mut world = HittableList::new();
-    let ground_material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    world.add(Box::new(Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        ground_material,
-    )));
-    world
-}
-
-fn create_cube_scene() -> HittableList {
-    let mut world = create_ground_scene();
-    let cube_material = Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1));
-    let cube = Cube::new(
-        Point3::new(-1.0, 0.0, -1.0),
-        Point3::new(1.0, 2.0, 1.0),
-        cube_material,
-    );
-    world.add(Box::new(cube));
-    world
-}
-
-fn create_cylinder_scene() -> HittableList {
-    let mut world = create_ground_scene();
-    let cylinder_material = Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    let cylinder = Cylinder::new(
-        Point3::new(-4.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        0.8,
-        4.0,
-        cylinder_material,
-    );
-    world.add(Box::new(cylinder));
-    world
-}
-
-fn create_sphere_scene() -> HittableList {
-    let mut world = create_ground_scene();
-    let material3 = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.2));
-    world.add(Box::new(Sphere::new(
-        Point3::new(4.0, 1.0, 0.0),
-        1.0,
-        material3,
-    )));
-    world
-}
-
-fn create_plane_scene() -> HittableList {
-    let mut world = create_ground_scene();
-    // let plane_material = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.8));
-    let plane_material = Rc::new(Dielectric::new(0.3));
-    let rect = Rectangle::new(
-        Point3::new(0.0, 0.1, -2.0),  // Positionnement plus bas
-        Vec3::new(2.0, 0.0, 0.0),        // Largeur
-        Vec3::new(0.0, 0.0, -2.0),        // Profondeur
-        plane_material,
-    );
-    world.add(Box::new(rect));
-    world
-}
-
-fn render_all_individual_images(cam: &Camera, image_width: i32, image_height: i32, samples_per_pixel: i32, max_depth: i32) {
-    let cube_scene = create_cube_scene();
-    save_scene_image(cam, &cube_scene, image_width, image_height, samples_per_pixel, max_depth, "image/cube.ppm");
-
-    let cylinder_scene = create_cylinder_scene();
-    save_scene_image(cam, &cylinder_scene, image_width, image_height, samples_per_pixel, max_depth, "image/cylinder.ppm");
-
-    let sphere_scene = create_sphere_scene();
-    save_scene_image(cam, &sphere_scene, image_width, image_height, samples_per_pixel, max_depth, "image/sphere.ppm");
-
-    let plane_scene = create_plane_scene();
-    save_scene_image(cam, &plane_scene, image_width, image_height, samples_per_pixel, max_depth, "image/flat-plane.ppm");
-
-    let scene = create_world_with_scene();
-    save_scene_image(cam, &scene, image_width, image_height, samples_per_pixel, max_depth, "image/scene.ppm");
-
-    let flat_and_cube_scene = create_flat_plane_and_cube();
-    save_scene_image(cam, &flat_and_cube_scene, image_width, image_height, samples_per_pixel, max_depth, "image/flat_and_cube.ppm");
-
-}
-
-fn create_flat_plane_and_cube() -> HittableList {
-    let mut world = create_ground_scene();
-
-    // Ajout du cube
-    let cube_material = Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1));
-    let cube = Cube::new(
-        Point3::new(-1.0, 0.0, -1.0),
-        Point3::new(1.0, 2.0, 1.0),
-        cube_material,
-    );
-    world.add(Box::new(cube));
-
-
-    // Ajout de la surface plane
-    // let plane_material = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.8));
-    let plane_material = Rc::new(Dielectric::new(0.3));
-    let rect = Rectangle::new(
-        Point3::new(3.0, 0.1, -1.0),  
-        Vec3::new(2.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, -2.0), 
-        plane_material,
-    );
-    world.add(Box::new(rect));
-
-    world
+// Mode de débogage d'une valeur de --debug. La profondeur va jusqu'au double
+// de la distance du point visé, le nombre de tests d'intersection jusqu'à 32.
+fn debug_mode(name: &str, key: &CameraKey) -> DebugMode {
+    match name {
+        "normal" => DebugMode::Normal,
+        "uv" => DebugMode::Uv,
+        "depth" => DebugMode::Depth { far: 2.0 * key.focus_distance },
+        "front-face" => DebugMode::FrontFace,
+        "material" => DebugMode::MaterialId,
+        _ => DebugMode::IntersectionTests { max: 32 },
+    }
 }
 
-fn create_world_with_scene() -> HittableList {
-    let mut world = create_ground_scene();
-
-    // Ajout du cube
-    let cube_material = Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1));
-    let cube = Cube::new(
-        Point3::new(-1.0, 0.0, -1.0),
-        Point3::new(1.0, 2.0, 1.0),
-        cube_material,
-    );
-    world.add(Box::new(cube));
-
-    // Ajout du cylindre
-    let cylinder_material = Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    let cylinder = Cylinder::new(
-        Point3::new(-4.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        0.8,
-        4.0,
-        cylinder_material,
-    );
-    world.add(Box::new(cylinder));
-
-
-
-    // Ajout de la sphère
-    let material3 = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-    world.add(Box::new(Sphere::new(
-        Point3::new(4.0, 1.0, 0.0),
-        1.0,
-        material3,
-    )));
-
-
-
-    // Ajout de la surface plane
-    // let plane_material = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.8));
-    let plane_material = Rc::new(Dielectric::new(0.3));
-    let rect = Rectangle::new(
-        Point3::new(6.0, 0.1, 0.0),  
-        Vec3::new(2.0, 0.0, 0.0),
-        Vec3::new(0.0, 0.0, -2.0), 
-        plane_material,
-    );
-    world.add(Box::new(rect));
-
-    world
+// Rendu décrit par les options, qui affiche son avancement et ses messages
+fn render_job(options: &Options) -> RenderJob {
+    let mut job = RenderJob::new(options.camera.clone())
+        .with_samples(options.samples_per_pixel)
+        .with_progress(print_progress)
+        .with_messages(|message| eprintln!("\r{:<60}", message));
+    if let Some(region) = options.region {
+        job = job.with_region(region);
+    }
+    if let Some(name) = options.debug.clone() {
+        job = job.with_debug_mode(move |key| debug_mode(&name, key));
+    }
+    if let Some(margin) = options.auto_frame {
+        job = job.with_auto_frame(margin, options.view_direction);
+    }
+    if let Some(preset) = options.animation {
+        job = job.with_animation(preset, options.interpolation, options.frames);
+    }
+    if let Some(interval) = options.preview_interval {
+        job = job.with_previews(interval);
+    }
+    if let Some(interval) = options.checkpoint_interval {
+        job = job.with_checkpoints(interval);
+    }
+    if options.resume {
+        job = job.with_resume();
+    }
+    if options.composite {
+        job = job.with_composite();
+    }
+    if options.stats_json {
+        job = job.with_stats_json();
+    }
+    job
 }
 
 fn main() {
@@ -260,69 +246,53 @@ fn main() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
-        eprintln!("Usage: cargo run <object_name>");
-        eprintln!("object_name must be one of: sphere, cube, cylinder, flat, scene, flat_and_cube, all");
-        return;
+        eprintln!("{}", USAGE);
+        eprintln!("{}", OBJECT_NAMES);
+        eprintln!("{}", PROJECTIONS);
+        process::exit(2);
     }
 
-    // Vérification et création du dossier 'image'
-    let output_dir = "image";
-    if fs::metadata(output_dir).is_err() {
-        fs::create_dir_all(output_dir).expect("Failed to create 'image' directory");
-    }
-
-    // Configuration commune de la caméra et du rendu
-    const ASPECT_RATIO: f64 = 16.0 / 9.0;
-    const IMAGE_WIDTH: i32 = 400;
-    const IMAGE_HEIGHT: i32 = ((IMAGE_WIDTH as f64) / ASPECT_RATIO) as i32;
-    const SAMPLES_PER_PIXEL: i32 = 100;
-    const MAX_DEPTH: i32 = 50;
-
-    let lookfrom = Point3::new(6.0, 12.0, -20.0);
-    let lookat = Point3::new(0.0, 1.0, 0.0);
-    let vup = Vec3::new(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let aperture = 0.0;
-    let camera = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        40.0,
-        ASPECT_RATIO,
-        aperture,
-        dist_to_focus,
-    );
+    let options = match parse_options(&args[2..]) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
 
     let object_name = &args[1];
-
-    if object_name == "all" {
-        render_all_individual_images(&camera, IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH);
+    if object_name == "all" && options.animation.is_some() {
+        eprintln!("Error: Animations render a single scene");
+        process::exit(2);
+    }
+    let object_names: Vec<&str> = if object_name == "all" {
+        SCENES.to_vec()
+    } else if SCENES.contains(&object_name.as_str()) {
+        vec![object_name.as_str()]
     } else {
-        let image_name = match object_name.as_str() {
-            "sphere" => "image/sphere.ppm",
-            "cube" => "image/cube.ppm",
-            "cylinder" => "image/cylinder.ppm",
-            "flat" => "image/flat-plane.ppm",
-            "scene" => "image/scene.ppm",
-            "flat_and_cube" => "image/flat_and_cube.ppm",
-            _ => {
-                eprintln!("Error: Unknown object '{}'", object_name);
-                eprintln!("object_name must be one of: sphere, cube, cylinder, flat, scene, flat_and_cube, all");
-                return;
-            }
-        };
+        eprintln!("Error: Unknown object '{}'", object_name);
+        eprintln!("{}", OBJECT_NAMES);
+        process::exit(2);
+    };
 
-        let world = match object_name.as_str() {
-            "sphere" => create_sphere_scene(),
-            "cube" => create_cube_scene(),
-            "cylinder" => create_cylinder_scene(),
-            "flat" => create_plane_scene(),
-            "scene" => create_world_with_scene(),
-            "flat_and_cube" => create_flat_plane_and_cube(),
-            _ => unreachable!(),
-        };
+    // Vérification et création du dossier 'image'
+    let output_dir = "image";
+    if let Err(e) = fs::create_dir_all(output_dir) {
+        eprintln!("Error: {}", Error::io(output_dir, e));
+        process::exit(1);
+    }
 
-        // Sauvegarde l'image de l'objet demandé
-        save_scene_image(&camera, &world, IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, image_name);
+    let job = render_job(&options);
+    for name in object_names {
+        let mut image_name = image_name(name);
+        // Les rendus de débogage n'écrasent pas l'image de la scène : image/sphere_normal.ppm, ...
+        if let Some(mode) = &options.debug {
+            image_name = format!("{}_{}.ppm", image_name.trim_end_matches(".ppm"), mode);
+        }
+        if let Err(e) = job.render(name, &image_name) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
     }
 }
@@ -1,6 +1,12 @@
+use std::rc::Rc;
+
 use crate::color::Color;
 use crate::hittable::HitRecord;
+use crate::microfacet;
+use crate::normal_map::NormalPerturbation;
 use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+use crate::vec3::Vec3;
 use crate::{common, vec3};
  
 pub trait Material {
@@ -11,14 +17,38 @@ pub trait Material {
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool;
+
+    // Normal used for shading; materials with a normal or bump map override this
+    fn shading_normal(&self, rec: &HitRecord) -> Vec3 {
+        rec.normal
+    }
+
+    // Light emitted by the surface itself; only emissive materials override this
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    // Phase function value for light arriving from `light_dir` (a unit vector
+    // towards the light) and scattered back along `r_in`. Only the phase functions
+    // of participating media override this: their hit records have no real normal,
+    // so surfaces return None and are lit by the cosine to their normal instead.
+    fn phase(&self, _r_in: &Ray, _light_dir: Vec3) -> Option<f64> {
+        None
+    }
 }
  
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Rc<dyn Texture>,
 }
  
 impl Lambertian {
     pub fn new(a: Color) -> Lambertian {
+        Lambertian {
+            albedo: Rc::new(SolidColor::new(a)),
+        }
+    }
+ 
+    pub fn textured(a: Rc<dyn Texture>) -> Lambertian {
         Lambertian { albedo: a }
     }
 }
@@ -26,7 +56,7 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _r_in: &Ray,
+        r_in: &Ray,
         rec: &HitRecord,
         attenuation: &mut Color,
         scattered: &mut Ray,
@@ -38,8 +68,8 @@ impl Material for Lambertian {
             scatter_direction = rec.normal;
         }
  
-        *attenuation = self.albedo;
-        *scattered = Ray::new(rec.p, scatter_direction);
+        *attenuation = self.albedo.value(rec.u, rec.v, &rec.p);
+        *scattered = Ray::new(rec.p, scatter_direction, r_in.time());
         true
     }
 }
@@ -69,11 +99,99 @@ impl Material for Metal {
         let reflected = vec3::reflect(vec3::unit_vector(r_in.direction()), rec.normal);
  
         *attenuation = self.albedo;
-        *scattered = Ray::new(rec.p, reflected + self.fuzz * vec3::random_in_unit_sphere());
+        *scattered = Ray::new(rec.p, reflected + self.fuzz * vec3::random_in_unit_sphere(), r_in.time());
         vec3::dot(scattered.direction(), rec.normal) > 0.0
     }
 }
  
+// Rough conductor using the GGX microfacet distribution. Unlike `Metal`, the
+// reflected energy follows the Fresnel equations for a complex index of
+// refraction (n + ik) and the microfacet masking-shadowing term.
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    roughness: f64,
+    anisotropy: f64,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: f64, anisotropy: f64) -> Conductor {
+        Conductor {
+            eta,
+            k,
+            roughness,
+            anisotropy,
+        }
+    }
+
+    // Complex IOR presets, sampled at roughly 650nm, 550nm and 450nm
+    pub fn gold(roughness: f64, anisotropy: f64) -> Conductor {
+        Conductor::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+            anisotropy,
+        )
+    }
+
+    pub fn copper(roughness: f64, anisotropy: f64) -> Conductor {
+        Conductor::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+            anisotropy,
+        )
+    }
+
+    pub fn aluminium(roughness: f64, anisotropy: f64) -> Conductor {
+        Conductor::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+            anisotropy,
+        )
+    }
+
+    pub fn silver(roughness: f64, anisotropy: f64) -> Conductor {
+        Conductor::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+            anisotropy,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let wo = microfacet::to_local(rec, -vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        let (ax, ay) = microfacet::roughness_to_alpha(self.roughness, self.anisotropy);
+        let h = microfacet::sample_vndf(wo, ax, ay);
+        let wi = vec3::reflect(-wo, h);
+        if wi.z() <= 0.0 {
+            return false;
+        }
+
+        // With visible normal sampling the estimator reduces to F * G2 / G1(wo)
+        let fresnel = microfacet::fresnel_conductor(vec3::dot(wo, h), self.eta, self.k);
+        let g = microfacet::smith_g2(wo, wi, ax, ay) / microfacet::smith_g1(wo, ax, ay);
+
+        *attenuation = g * fresnel;
+        *scattered = Ray::new(rec.p, microfacet::to_world(rec, wi), r_in.time());
+        true
+    }
+}
+
 pub struct Dielectric {
     ir: f64, // Index of refraction
 }
@@ -121,7 +239,225 @@ impl Material for Dielectric {
         };
  
         *attenuation = Color::new(1.0, 1.0, 1.0);
-        *scattered = Ray::new(rec.p, direction);
+        *scattered = Ray::new(rec.p, direction, r_in.time());
+        true
+    }
+}
+
+// Phase function of a participating medium scattering light uniformly in all directions
+pub struct Isotropic {
+    albedo: Color,
+}
+
+impl Isotropic {
+    pub fn new(a: Color) -> Isotropic {
+        Isotropic { albedo: a }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        *attenuation = self.albedo;
+        *scattered = Ray::new(rec.p, vec3::random_unit_vector(), r_in.time());
+        true
+    }
+
+    fn phase(&self, _r_in: &Ray, _light_dir: Vec3) -> Option<f64> {
+        Some(1.0 / (4.0 * common::PI))
+    }
+}
+
+// Henyey-Greenstein phase function: g > 0 favours forward scattering (fog, haze),
+// g < 0 backward scattering and g = 0 is isotropic
+pub struct HenyeyGreenstein {
+    albedo: Color,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(a: Color, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            albedo: a,
+            g: common::clamp(g, -0.99, 0.99),
+        }
+    }
+
+    // Sample the cosine of the angle between the incoming and scattered directions
+    fn sample_cos_theta(&self) -> f64 {
+        let xi = common::random_double();
+        if self.g.abs() < 1.0e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+        let g = self.g;
+        let sqr_term = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        common::clamp((1.0 + g * g - sqr_term * sqr_term) / (2.0 * g), -1.0, 1.0)
+    }
+
+    // Density of the directions scattered at an angle whose cosine is `cos_theta`
+    fn evaluate(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * common::PI * denom * denom.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let forward = vec3::unit_vector(r_in.direction());
+        let cos_theta = self.sample_cos_theta();
+        let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+        let phi = 2.0 * common::PI * common::random_double();
+
+        // Orthonormal basis around the direction of propagation
+        let helper = if forward.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t = vec3::unit_vector(vec3::cross(helper, forward));
+        let b = vec3::cross(forward, t);
+        let direction = sin_theta * f64::cos(phi) * t + sin_theta * f64::sin(phi) * b + cos_theta * forward;
+
+        *attenuation = self.albedo;
+        *scattered = Ray::new(rec.p, direction, r_in.time());
+        true
+    }
+
+    fn phase(&self, r_in: &Ray, light_dir: Vec3) -> Option<f64> {
+        // Light coming from `light_dir` goes on along the ray towards the camera,
+        // like a ray scattered from `r_in` towards the light
+        Some(self.evaluate(vec3::dot(vec3::unit_vector(r_in.direction()), light_dir)))
+    }
+}
+
+// Wraps any material and perturbs its shading normal with a normal or bump map
+pub struct NormalMapped {
+    base: Rc<dyn Material>,
+    detail: Rc<dyn NormalPerturbation>,
+}
+
+impl NormalMapped {
+    pub fn new(base: Rc<dyn Material>, detail: Rc<dyn NormalPerturbation>) -> NormalMapped {
+        NormalMapped { base, detail }
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        self.base.scatter(r_in, rec, attenuation, scattered)
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.base.emitted(rec)
+    }
+
+    fn shading_normal(&self, rec: &HitRecord) -> Vec3 {
+        let mut detailed = rec.clone();
+        detailed.normal = self.base.shading_normal(rec);
+        self.detail.perturb(&detailed)
+    }
+}
+
+// Rough dielectric using GGX microfacet reflection and transmission. Light is
+// absorbed inside the medium following the Beer-Lambert law: `absorption` is the
+// absorption coefficient per unit of distance, so thicker glass tints more.
+pub struct RoughDielectric {
+    ir: f64,
+    roughness: f64,
+    absorption: Color,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: f64, roughness: f64, absorption: Color) -> RoughDielectric {
+        RoughDielectric {
+            ir: index_of_refraction,
+            roughness,
+            absorption,
+        }
+    }
+
+    // Tinted glass that lets `transmittance` through after one unit of distance
+    pub fn tinted(index_of_refraction: f64, roughness: f64, transmittance: Color) -> RoughDielectric {
+        let sigma = |t: f64| -f64::ln(common::clamp(t, 1.0e-4, 1.0));
+        RoughDielectric::new(
+            index_of_refraction,
+            roughness,
+            Color::new(
+                sigma(transmittance.x()),
+                sigma(transmittance.y()),
+                sigma(transmittance.z()),
+            ),
+        )
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        // A back face hit means the ray travelled inside the medium up to here
+        *attenuation = if rec.front_face {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            let distance = rec.t * r_in.direction().length();
+            Color::new(
+                f64::exp(-self.absorption.x() * distance),
+                f64::exp(-self.absorption.y() * distance),
+                f64::exp(-self.absorption.z() * distance),
+            )
+        };
+
+        let wo = microfacet::to_local(rec, -vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        let eta = if rec.front_face { 1.0 / self.ir } else { self.ir };
+        let (ax, ay) = microfacet::roughness_to_alpha(self.roughness, 0.0);
+        let h = microfacet::sample_vndf(wo, ax, ay);
+        let cos_i = vec3::dot(wo, h);
+
+        // Choose between reflection and transmission proportionally to Fresnel
+        let wi = if microfacet::fresnel_dielectric(cos_i, eta) > common::random_double() {
+            let wi = vec3::reflect(-wo, h);
+            if wi.z() <= 0.0 {
+                return false;
+            }
+            wi
+        } else {
+            let wi = vec3::refract(-wo, h, eta);
+            if wi.z() >= 0.0 {
+                return false;
+            }
+            wi
+        };
+
+        let g = microfacet::smith_g2(wo, Vec3::new(wi.x(), wi.y(), wi.z().abs()), ax, ay)
+            / microfacet::smith_g1(wo, ax, ay);
+        *attenuation = g * *attenuation;
+        *scattered = Ray::new(rec.p, microfacet::to_world(rec, wi), r_in.time());
         true
     }
-}
\ No newline at end of file
+}
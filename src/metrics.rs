@@ -0,0 +1,198 @@
+use crate::color::Color;
+use crate::common;
+use crate::error::{Error, Result};
+use crate::image::Image;
+
+// Differences between an image and a reference image of the same size
+#[derive(Clone, Copy, Debug)]
+pub struct Comparison {
+    // Root mean squared error of the linear values
+    pub rmse: f64,
+    // Mean squared error relative to the squared reference values, which
+    // weighs errors in dark areas as much as in bright ones
+    pub rel_mse: f64,
+    // Peak signal to noise ratio in dB of the displayed values (gamma 2,
+    // clamped to [0, 1]), infinite for identical images
+    pub psnr: f64,
+    // Mean structural similarity of the displayed luminance, 1 for identical images
+    pub ssim: f64,
+}
+
+pub fn compare(image: &Image, reference: &Image) -> Result<Comparison> {
+    check_sizes(image, reference)?;
+    let pairs = || image.pixels().iter().zip(reference.pixels()).flat_map(|(a, b)| channels(*a).zip(channels(*b)));
+    let count = 3.0 * image.pixels().len() as f64;
+
+    let mse = pairs().map(|(a, b)| (a - b) * (a - b)).sum::<f64>() / count;
+    let rel_mse = pairs().map(|(a, b)| (a - b) * (a - b) / (b * b + 0.01)).sum::<f64>() / count;
+    let display_mse = pairs().map(|(a, b)| (display(a) - display(b)).powi(2)).sum::<f64>() / count;
+    Ok(Comparison {
+        rmse: mse.sqrt(),
+        rel_mse,
+        psnr: -10.0 * display_mse.log10(),
+        ssim: ssim(image, reference),
+    })
+}
+
+// Per pixel RMSE of the linear values in false color, from black through
+// purple and orange to light yellow at `max_error` (by default the largest
+// error of the image)
+pub fn error_heatmap(image: &Image, reference: &Image, max_error: Option<f64>) -> Result<Image> {
+    check_sizes(image, reference)?;
+    let errors: Vec<f64> = image
+        .pixels()
+        .iter()
+        .zip(reference.pixels())
+        .map(|(a, b)| ((*a - *b).length_squared() / 3.0).sqrt())
+        .collect();
+    let max_error = max_error.unwrap_or_else(|| errors.iter().cloned().fold(0.0, f64::max));
+
+    let pixels = errors
+        .iter()
+        .map(|&error| {
+            let c = false_color(if max_error > 0.0 { error / max_error } else { 0.0 });
+            // Back to linear values, which the image writers gamma correct
+            c * c
+        })
+        .collect();
+    Ok(Image::new(image.width(), image.height(), pixels))
+}
+
+// Displayed color of `x` in [0, 1] on the heatmap scale: black, purple,
+// orange, then light yellow
+pub fn false_color(x: f64) -> Color {
+    let stops = [
+        Color::new(0.0, 0.0, 0.02),
+        Color::new(0.34, 0.06, 0.38),
+        Color::new(0.73, 0.21, 0.33),
+        Color::new(0.98, 0.55, 0.04),
+        Color::new(0.99, 1.0, 0.64),
+    ];
+    let position = common::clamp(x, 0.0, 1.0) * (stops.len() - 1) as f64;
+    let i = (position as usize).min(stops.len() - 2);
+    stops[i] + (position - i as f64) * (stops[i + 1] - stops[i])
+}
+
+// Both images must have the same size, and some pixels
+fn check_sizes(image: &Image, reference: &Image) -> Result<()> {
+    if image.width() != reference.width() || image.height() != reference.height() {
+        return Err(Error::SizeMismatch {
+            size: (image.width(), image.height()),
+            reference_size: (reference.width(), reference.height()),
+        });
+    }
+    if image.pixels().is_empty() {
+        return Err(Error::EmptyImage);
+    }
+    Ok(())
+}
+
+fn channels(c: Color) -> impl Iterator<Item = f64> {
+    [c.x(), c.y(), c.z()].into_iter()
+}
+
+// Displayed value of a linear one, as written in the images
+fn display(x: f64) -> f64 {
+    common::clamp(x, 0.0, 1.0).sqrt()
+}
+
+// Mean SSIM over 11x11 Gaussian windows (sigma 1.5) of the displayed
+// luminance, after Wang et al. 2004. Windows are clamped at the borders.
+fn ssim(image: &Image, reference: &Image) -> f64 {
+    let luminance = |image: &Image| -> Vec<f64> {
+        let y = |c: &Color| 0.2126 * display(c.x()) + 0.7152 * display(c.y()) + 0.0722 * display(c.z());
+        image.pixels().iter().map(y).collect()
+    };
+    let (width, height) = (image.width(), image.height());
+    let x = luminance(image);
+    let y = luminance(reference);
+    let product = |a: &[f64], b: &[f64]| -> Vec<f64> { a.iter().zip(b).map(|(a, b)| a * b).collect() };
+
+    let blur = |values: &[f64]| gaussian_blur(values, width, height);
+    let mean_x = blur(&x);
+    let mean_y = blur(&y);
+    let mean_xx = blur(&product(&x, &x));
+    let mean_yy = blur(&product(&y, &y));
+    let mean_xy = blur(&product(&x, &y));
+
+    let c1 = 0.01 * 0.01;
+    let c2 = 0.03 * 0.03;
+    let total: f64 = (0..width * height)
+        .map(|i| {
+            let (mx, my) = (mean_x[i], mean_y[i]);
+            let variance_x = mean_xx[i] - mx * mx;
+            let variance_y = mean_yy[i] - my * my;
+            let covariance = mean_xy[i] - mx * my;
+            ((2.0 * mx * my + c1) * (2.0 * covariance + c2))
+                / ((mx * mx + my * my + c1) * (variance_x + variance_y + c2))
+        })
+        .sum();
+    total / (width * height) as f64
+}
+
+fn gaussian_blur(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    const RADIUS: isize = 5;
+    let weights: Vec<f64> = (-RADIUS..=RADIUS).map(|k| (-(k * k) as f64 / (2.0 * 1.5 * 1.5)).exp()).collect();
+    let sum: f64 = weights.iter().sum();
+    let weights: Vec<f64> = weights.iter().map(|w| w / sum).collect();
+
+    // Separable: along the rows, then along the columns
+    let pass = |input: &[f64], horizontal: bool| -> Vec<f64> {
+        let mut output = vec![0.0; input.len()];
+        for row in 0..height {
+            for column in 0..width {
+                output[row * width + column] = (-RADIUS..=RADIUS)
+                    .zip(&weights)
+                    .map(|(k, w)| {
+                        let (r, c) = if horizontal {
+                            (row, (column as isize + k).clamp(0, width as isize - 1) as usize)
+                        } else {
+                            ((row as isize + k).clamp(0, height as isize - 1) as usize, column)
+                        };
+                        w * input[r * width + c]
+                    })
+                    .sum();
+            }
+        }
+        output
+    };
+    pass(&pass(values, true), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    fn gray_image(width: usize, height: usize, value: f64) -> Image {
+        Image::new(width, height, vec![Color::new(value, value, value); width * height])
+    }
+
+    #[test]
+    fn identical_images() {
+        let image = gray_image(16, 16, 0.25);
+        let comparison = compare(&image, &image).unwrap();
+        assert_close(comparison.rmse, 0.0);
+        assert_close(comparison.rel_mse, 0.0);
+        assert_eq!(comparison.psnr, f64::INFINITY);
+        assert_close(comparison.ssim, 1.0);
+    }
+
+    #[test]
+    fn uniform_error() {
+        // Displayed values 0.5 and 0.6
+        let comparison = compare(&gray_image(4, 4, 0.36), &gray_image(4, 4, 0.25)).unwrap();
+        assert_close(comparison.rmse, 0.11);
+        assert_close(comparison.rel_mse, 0.11 * 0.11 / (0.25 * 0.25 + 0.01));
+        assert_close(comparison.psnr, 20.0);
+    }
+
+    #[test]
+    fn reject_other_sizes_and_empty_images() {
+        let result = compare(&gray_image(4, 4, 0.5), &gray_image(4, 2, 0.5));
+        assert!(matches!(result, Err(Error::SizeMismatch { .. })));
+        let empty = gray_image(0, 0, 0.5);
+        assert!(matches!(compare(&empty, &empty), Err(Error::EmptyImage)));
+        assert!(matches!(error_heatmap(&empty, &empty, None), Err(Error::EmptyImage)));
+    }
+}
@@ -0,0 +1,126 @@
+// GGX / Trowbridge-Reitz microfacet helpers shared by the rough materials.
+//
+// All directions are expressed in the local shading frame of the hit point,
+// where x follows the tangent, y the bitangent and z the shading normal.
+
+use crate::color::Color;
+use crate::common;
+use crate::hittable::HitRecord;
+use crate::vec3::{self, Vec3};
+
+pub fn to_local(rec: &HitRecord, v: Vec3) -> Vec3 {
+    Vec3::new(
+        vec3::dot(v, rec.tangent),
+        vec3::dot(v, rec.bitangent),
+        vec3::dot(v, rec.normal),
+    )
+}
+
+pub fn to_world(rec: &HitRecord, v: Vec3) -> Vec3 {
+    v.x() * rec.tangent + v.y() * rec.bitangent + v.z() * rec.normal
+}
+
+// Map a perceptual roughness and anisotropy in [0, 1] to the GGX alpha_x/alpha_y
+pub fn roughness_to_alpha(roughness: f64, anisotropy: f64) -> (f64, f64) {
+    let r = common::clamp(roughness, 0.0, 1.0);
+    let aspect = f64::sqrt(1.0 - 0.9 * common::clamp(anisotropy, 0.0, 1.0));
+    let alpha = r * r;
+    ((alpha / aspect).max(1.0e-3), (alpha * aspect).max(1.0e-3))
+}
+
+// Normal distribution function D(h)
+pub fn ggx_d(h: Vec3, ax: f64, ay: f64) -> f64 {
+    let e = (h.x() / ax).powi(2) + (h.y() / ay).powi(2) + h.z() * h.z();
+    1.0 / (common::PI * ax * ay * e * e)
+}
+
+// Density of the visible normal `h` as sampled by `sample_vndf`
+pub fn vndf_pdf(wo: Vec3, h: Vec3, ax: f64, ay: f64) -> f64 {
+    smith_g1(wo, ax, ay) * f64::max(0.0, vec3::dot(wo, h)) * ggx_d(h, ax, ay) / wo.z()
+}
+
+// Smith Lambda function for the anisotropic GGX distribution
+pub fn smith_lambda(w: Vec3, ax: f64, ay: f64) -> f64 {
+    let cos2 = w.z() * w.z();
+    if cos2 == 0.0 {
+        return common::INFINITY;
+    }
+    let tan2 = ((ax * w.x()).powi(2) + (ay * w.y()).powi(2)) / cos2;
+    0.5 * (-1.0 + f64::sqrt(1.0 + tan2))
+}
+
+pub fn smith_g1(w: Vec3, ax: f64, ay: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(w, ax, ay))
+}
+
+// Height-correlated masking-shadowing term
+pub fn smith_g2(wo: Vec3, wi: Vec3, ax: f64, ay: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(wo, ax, ay) + smith_lambda(wi, ax, ay))
+}
+
+// Sample a microfacet normal from the distribution of normals visible from `wo`
+// (Heitz 2018, "Sampling the GGX Distribution of Visible Normals")
+pub fn sample_vndf(wo: Vec3, ax: f64, ay: f64) -> Vec3 {
+    let vh = vec3::unit_vector(Vec3::new(ax * wo.x(), ay * wo.y(), wo.z()));
+
+    let lensq = vh.x() * vh.x() + vh.y() * vh.y();
+    let t1 = if lensq > 0.0 {
+        Vec3::new(-vh.y(), vh.x(), 0.0) / f64::sqrt(lensq)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let t2 = vec3::cross(vh, t1);
+
+    let r = f64::sqrt(common::random_double());
+    let phi = 2.0 * common::PI * common::random_double();
+    let p1 = r * f64::cos(phi);
+    let s = 0.5 * (1.0 + vh.z());
+    let p2 = (1.0 - s) * f64::sqrt(1.0 - p1 * p1) + s * r * f64::sin(phi);
+
+    let nh = p1 * t1 + p2 * t2 + f64::sqrt(f64::max(0.0, 1.0 - p1 * p1 - p2 * p2)) * vh;
+    vec3::unit_vector(Vec3::new(ax * nh.x(), ay * nh.y(), nh.z().max(1.0e-6)))
+}
+
+// Exact Fresnel reflectance of a dielectric interface, `eta` being the ratio of
+// the incident over the transmitted index of refraction
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = common::clamp(cos_i, 0.0, 1.0);
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+    if sin2_t >= 1.0 {
+        return 1.0; // Total internal reflection
+    }
+    let cos_t = f64::sqrt(1.0 - sin2_t);
+
+    let rs = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let rp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (rs * rs + rp * rp)
+}
+
+// Exact Fresnel reflectance of a conductor with complex index of refraction n + ik
+pub fn fresnel_conductor(cos_theta: f64, eta: Color, k: Color) -> Color {
+    Color::new(
+        fresnel_conductor_channel(cos_theta, eta.x(), k.x()),
+        fresnel_conductor_channel(cos_theta, eta.y(), k.y()),
+        fresnel_conductor_channel(cos_theta, eta.z(), k.z()),
+    )
+}
+
+fn fresnel_conductor_channel(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = common::clamp(cos_theta * cos_theta, 0.0, 1.0);
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = f64::sqrt(t0 * t0 + 4.0 * eta2 * k2);
+    let t1 = a2_plus_b2 + cos2;
+    let a = f64::sqrt(f64::max(0.0, 0.5 * (a2_plus_b2 + t0)));
+    let t2 = 2.0 * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
@@ -0,0 +1,114 @@
+use std::rc::Rc;
+
+use crate::aabb::{self, Aabb};
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+// Sphere whose center moves linearly from `center0` at `time0` to `center1` at `time1`
+pub struct MovingSphere {
+    center0: Point3,
+    center1: Point3,
+    time0: f64,
+    time1: f64,
+    radius: f64,
+    mat: Rc<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        mat: Rc<dyn Material>,
+    ) -> MovingSphere {
+        MovingSphere {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            mat,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        if self.time1 <= self.time0 {
+            return self.center0;
+        }
+        self.center0 + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+
+    fn roots(&self, r: &Ray, center: Point3) -> Option<(f64, f64)> {
+        let oc = r.origin() - center;
+        let a = r.direction().length_squared();
+        let half_b = vec3::dot(oc, r.direction());
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let sqrt_d = f64::sqrt(discriminant);
+        Some(((-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a))
+    }
+
+    fn fill_record(&self, r: &Ray, center: Point3, t: f64, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(t);
+        let outward_normal = (rec.p - center) / self.radius;
+        rec.set_face_normal(r, outward_normal);
+
+        // Same parametrization as the static sphere
+        let theta = f64::acos(common::clamp(-outward_normal.y(), -1.0, 1.0));
+        let phi = f64::atan2(-outward_normal.z(), outward_normal.x()) + common::PI;
+        rec.u = phi / (2.0 * common::PI);
+        rec.v = theta / common::PI;
+        rec.set_tangent_frame(Vec3::new(outward_normal.z(), 0.0, -outward_normal.x()));
+        rec.mat = Some(self.mat.clone());
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::MovingSphere);
+        let center = self.center(r.time());
+        let Some((t0, t1)) = self.roots(r, center) else {
+            return false;
+        };
+
+        let t = if t0 > t_min && t0 < t_max {
+            t0
+        } else if t1 > t_min && t1 < t_max {
+            t1
+        } else {
+            return false;
+        };
+        self.fill_record(r, center, t, rec);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        // Box around the whole sweep over the motion interval
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        let box0 = Aabb::new(self.center0 - r, self.center0 + r);
+        let box1 = Aabb::new(self.center1 - r, self.center1 + r);
+        *output_box = aabb::surrounding_box(&box0, &box1);
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        let center = self.center(r.time());
+        let Some((t0, t1)) = self.roots(r, center) else {
+            return Vec::new();
+        };
+        let mut span = Span::default();
+        self.fill_record(r, center, t0, &mut span.enter);
+        self.fill_record(r, center, t1, &mut span.exit);
+        vec![span]
+    }
+}
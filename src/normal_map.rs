@@ -0,0 +1,78 @@
+use std::rc::Rc;
+
+use crate::hittable::HitRecord;
+use crate::texture::Texture;
+use crate::vec3::{self, Vec3};
+
+// Perturbs the shading normal of a hit point using its tangent frame
+pub trait NormalPerturbation {
+    fn perturb(&self, rec: &HitRecord) -> Vec3;
+}
+
+// Tangent-space normal map: red/green/blue encode the tangent, bitangent and normal
+// components remapped from [-1, 1] to [0, 1]
+pub struct NormalMap {
+    map: Rc<dyn Texture>,
+    strength: f64,
+}
+
+impl NormalMap {
+    pub fn new(map: Rc<dyn Texture>, strength: f64) -> NormalMap {
+        NormalMap { map, strength }
+    }
+}
+
+impl NormalPerturbation for NormalMap {
+    fn perturb(&self, rec: &HitRecord) -> Vec3 {
+        let texel = self.map.value(rec.u, rec.v, &rec.p);
+        let x = (2.0 * texel.x() - 1.0) * self.strength;
+        let y = (2.0 * texel.y() - 1.0) * self.strength;
+        let z = 2.0 * texel.z() - 1.0;
+
+        let n = x * rec.tangent + y * rec.bitangent + z * rec.normal;
+        keep_in_hemisphere(n, rec.normal)
+    }
+}
+
+// Height based bump map: the normal is tilted along the gradient of the texture
+// luminance, estimated with finite differences in uv space
+pub struct BumpMap {
+    height: Rc<dyn Texture>,
+    scale: f64,
+    delta: f64,
+}
+
+impl BumpMap {
+    pub fn new(height: Rc<dyn Texture>, scale: f64) -> BumpMap {
+        BumpMap {
+            height,
+            scale,
+            delta: 1.0e-3,
+        }
+    }
+
+    fn height_at(&self, u: f64, v: f64, rec: &HitRecord) -> f64 {
+        let c = self.height.value(u, v, &rec.p);
+        0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+    }
+}
+
+impl NormalPerturbation for BumpMap {
+    fn perturb(&self, rec: &HitRecord) -> Vec3 {
+        let h = self.height_at(rec.u, rec.v, rec);
+        let dh_du = (self.height_at(rec.u + self.delta, rec.v, rec) - h) / self.delta;
+        let dh_dv = (self.height_at(rec.u, rec.v + self.delta, rec) - h) / self.delta;
+
+        let n = rec.normal - self.scale * (dh_du * rec.tangent + dh_dv * rec.bitangent);
+        keep_in_hemisphere(n, rec.normal)
+    }
+}
+
+// A perturbed normal pointing below the surface would make rays leak through it,
+// so fall back to the geometric normal in that case
+fn keep_in_hemisphere(n: Vec3, geometric: Vec3) -> Vec3 {
+    if n.near_zero() || vec3::dot(n, geometric) <= 0.0 {
+        return geometric;
+    }
+    vec3::unit_vector(n)
+}
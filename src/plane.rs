@@ -0,0 +1,69 @@
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+// Infinite plane through `point`. Texture coordinates are the distances along
+// two fixed in-plane axes, in world units, so a checker texture with a scale of
+// 1 gives one square per unit.
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    mat: Rc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, mat: Rc<dyn Material>) -> Plane {
+        let normal = vec3::unit_vector(normal);
+        let helper = if normal.x().abs() > 0.9 {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let bitangent = vec3::unit_vector(vec3::cross(normal, helper));
+        let tangent = vec3::cross(bitangent, normal);
+
+        Plane {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            mat,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Plane);
+        let denom = vec3::dot(self.normal, r.direction());
+        if denom.abs() < 1.0e-9 {
+            return false;
+        }
+
+        let t = vec3::dot(self.point - r.origin(), self.normal) / denom;
+        if t <= t_min || t >= t_max {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_face_normal(r, self.normal);
+        let local = rec.p - self.point;
+        rec.u = vec3::dot(local, self.tangent);
+        rec.v = vec3::dot(local, self.bitangent);
+        rec.set_tangent_frame(self.tangent);
+        rec.mat = Some(self.mat.clone());
+        true
+    }
+
+    fn bounding_box(&self, _output_box: &mut Aabb) -> bool {
+        false
+    }
+}
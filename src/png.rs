@@ -0,0 +1,550 @@
+use std::fs;
+use std::io::Write;
+
+use crate::error::{Error, Result};
+use crate::image;
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+// Decoded PNG: 8 or 16-bit samples scaled to [0, 1], three per pixel (gray is
+// repeated, alpha dropped), row by row from the top
+pub struct Png {
+    pub width: usize,
+    pub height: usize,
+    pub samples: Vec<f64>,
+}
+
+pub fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(SIGNATURE)
+}
+
+// Read a non interlaced PNG of any color type, with 8 or 16-bit samples (or
+// 8-bit palette indices)
+pub fn read_png(path: &str) -> Result<Png> {
+    let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+    let invalid = |msg: &str| Error::invalid_file(path, msg);
+    if !is_png(&bytes) {
+        return Err(invalid("not a PNG image"));
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+    let mut pos = SIGNATURE.len();
+    loop {
+        if pos + 12 > bytes.len() {
+            return Err(invalid("truncated chunk"));
+        }
+        let length = u32::from_be_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        let end = pos + 8 + length;
+        if end + 4 > bytes.len() {
+            return Err(invalid("truncated chunk"));
+        }
+        let kind = &bytes[pos + 4..pos + 8];
+        let data = &bytes[pos + 8..end];
+        let crc = u32::from_be_bytes(bytes[end..end + 4].try_into().unwrap());
+        if crc32(&bytes[pos + 4..end]) != crc {
+            return Err(invalid("bad chunk checksum"));
+        }
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data.to_vec()),
+            b"PLTE" => palette = data.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos = end + 4;
+    }
+
+    let header = header.ok_or_else(|| invalid("missing header"))?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize;
+    if width == 0 || height == 0 {
+        return Err(invalid("bad size"));
+    }
+    let (depth, color_type, interlace) = (header[8] as usize, header[9], header[12]);
+    if interlace != 0 {
+        return Err(invalid("interlaced images are not supported"));
+    }
+    let channels = match (color_type, depth) {
+        (0, 8 | 16) => 1,
+        (2, 8 | 16) => 3,
+        (3, 8) => 1,
+        (4, 8 | 16) => 2,
+        (6, 8 | 16) => 4,
+        _ => return Err(invalid("unsupported color type or bit depth")),
+    };
+
+    // Filtered rows, each with its filter byte. The size comes from the
+    // header, so it is checked before anything that large is allocated.
+    let pixel_bytes = channels * depth / 8;
+    let row_bytes = width.checked_mul(pixel_bytes).ok_or_else(|| invalid("image too large"))?;
+    let raw_bytes = (row_bytes + 1).checked_mul(height).ok_or_else(|| invalid("image too large"))?;
+    let raw = zlib_decompress(&compressed, raw_bytes).ok_or_else(|| invalid("bad compressed data"))?;
+    if raw.len() < raw_bytes {
+        return Err(invalid("truncated image data"));
+    }
+    let rows = unfilter(&raw, height, row_bytes, pixel_bytes).ok_or_else(|| invalid("bad row filter"))?;
+
+    let max = ((1u32 << depth) - 1) as f64;
+    let sample = |i: usize| match depth {
+        16 => u16::from_be_bytes([rows[2 * i], rows[2 * i + 1]]) as f64 / max,
+        _ => rows[i] as f64 / max,
+    };
+    let mut samples = Vec::with_capacity(width * height * 3);
+    for p in 0..width * height {
+        let first = p * channels;
+        match color_type {
+            3 => {
+                let index = rows[first] as usize * 3;
+                let rgb = palette.get(index..index + 3).ok_or_else(|| invalid("bad palette index"))?;
+                samples.extend(rgb.iter().map(|&c| c as f64 / 255.0));
+            }
+            0 | 4 => {
+                let gray = sample(first);
+                samples.extend([gray, gray, gray]);
+            }
+            _ => samples.extend([sample(first), sample(first + 1), sample(first + 2)]),
+        }
+    }
+    Ok(Png { width, height, samples })
+}
+
+// Write 8-bit RGB values, row by row from the top, as a PNG. The image data
+// is stored without compression, which any reader accepts.
+pub fn write_png(file_name: &str, width: usize, height: usize, rgb: &[u8]) -> Result<()> {
+    let mut raw = Vec::with_capacity(height * (3 * width + 1));
+    for row in rgb.chunks(3 * width) {
+        raw.push(0); // No filter
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    image::write_file(file_name, |writer| {
+        writer.write_all(SIGNATURE)?;
+        for (kind, data) in [(b"IHDR", header), (b"IDAT", zlib_store(&raw)), (b"IEND", Vec::new())] {
+            let mut chunk = kind.to_vec();
+            chunk.extend_from_slice(&data);
+            writer.write_all(&(data.len() as u32).to_be_bytes())?;
+            writer.write_all(&chunk)?;
+            writer.write_all(&crc32(&chunk).to_be_bytes())?;
+        }
+        Ok(())
+    })
+}
+
+// Undo the filter of every row (its first byte), returning the rows without
+// their filter byte
+fn unfilter(raw: &[u8], height: usize, row_bytes: usize, pixel_bytes: usize) -> Option<Vec<u8>> {
+    let mut rows = vec![0u8; height * row_bytes];
+    for y in 0..height {
+        let filter = raw[y * (row_bytes + 1)];
+        let line = &raw[y * (row_bytes + 1) + 1..(y + 1) * (row_bytes + 1)];
+        let (previous, current) = rows.split_at_mut(y * row_bytes);
+        let up = if y > 0 { &previous[(y - 1) * row_bytes..] } else { &[][..] };
+        let current = &mut current[..row_bytes];
+        for i in 0..row_bytes {
+            let a = if i >= pixel_bytes { current[i - pixel_bytes] } else { 0 };
+            let b = up.get(i).copied().unwrap_or(0);
+            let c = if i >= pixel_bytes { up.get(i - pixel_bytes).copied().unwrap_or(0) } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            };
+            current[i] = line[i].wrapping_add(predictor);
+        }
+    }
+    Some(rows)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// Zlib stream of uncompressed deflate blocks
+fn zlib_store(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() { vec![data] } else { data.chunks(65535).collect() };
+    for (i, block) in blocks.iter().enumerate() {
+        out.push((i + 1 == blocks.len()) as u8);
+        let length = block.len() as u16;
+        out.extend_from_slice(&length.to_le_bytes());
+        out.extend_from_slice(&(!length).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+// Decompressed data, of at most `limit` bytes
+fn zlib_decompress(data: &[u8], limit: usize) -> Option<Vec<u8>> {
+    let (cmf, flg) = (*data.first()?, *data.get(1)?);
+    if cmf & 0x0f != 8 || flg & 0x20 != 0 || !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return None;
+    }
+    let mut input = Bits { data, pos: 2, bit: 0 };
+    let out = inflate(&mut input, limit)?;
+    let end = input.pos + (input.bit > 0) as usize;
+    let checksum = u32::from_be_bytes(data.get(end..end + 4)?.try_into().ok()?);
+    (checksum == adler32(&out)).then_some(out)
+}
+
+// Deflate decoder (RFC 1951), after zlib's puff.c
+
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl Bits<'_> {
+    // Next `n` bits, least significant first
+    fn read(&mut self, n: u32) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..n {
+            let byte = *self.data.get(self.pos)?;
+            value |= (((byte >> self.bit) & 1) as u32) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Some(value)
+    }
+}
+
+// Canonical Huffman code: the number of codes of each length and the symbols
+// ordered by code
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, input: &mut Bits) -> Option<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= input.read(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+// Decompressed data, or None if it is invalid or longer than `limit` bytes
+fn inflate(input: &mut Bits, limit: usize) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let last = input.read(1)? == 1;
+        match input.read(2)? {
+            0 => {
+                // Stored block, starting on a byte boundary
+                if input.bit > 0 {
+                    input.bit = 0;
+                    input.pos += 1;
+                }
+                let header = input.data.get(input.pos..input.pos + 4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return None;
+                }
+                let start = input.pos + 4;
+                if out.len() + length as usize > limit {
+                    return None;
+                }
+                out.extend_from_slice(input.data.get(start..start + length as usize)?);
+                input.pos = start + length as usize;
+            }
+            1 => {
+                let mut lengths = [8u8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                inflate_block(input, &mut out, limit, &Huffman::new(&lengths), &Huffman::new(&[5; 30]))?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(input)?;
+                inflate_block(input, &mut out, limit, &literals, &distances)?;
+            }
+            _ => return None,
+        }
+        if last {
+            return Some(out);
+        }
+    }
+}
+
+fn read_dynamic_codes(input: &mut Bits) -> Option<(Huffman, Huffman)> {
+    let literal_count = input.read(5)? as usize + 257;
+    let distance_count = input.read(5)? as usize + 1;
+    let code_count = input.read(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[index] = input.read(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code.decode(input)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last()?, 3 + input.read(2)?),
+            17 => (0, 3 + input.read(3)?),
+            18 => (0, 11 + input.read(7)?),
+            _ => return None,
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() != literal_count + distance_count {
+        return None;
+    }
+    Some((Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..])))
+}
+
+fn inflate_block(
+    input: &mut Bits,
+    out: &mut Vec<u8>,
+    limit: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Option<()> {
+    loop {
+        if out.len() > limit {
+            return None;
+        }
+        let symbol = literals.decode(input)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Some(()),
+            _ => {
+                let index = symbol - 257;
+                let length = *LENGTH_BASE.get(index)? as usize + input.read(*LENGTH_EXTRA.get(index)? as u32)? as usize;
+                let index = distances.decode(input)? as usize;
+                let distance =
+                    *DISTANCE_BASE.get(index)? as usize + input.read(*DISTANCE_EXTRA.get(index)? as u32)? as usize;
+                if distance > out.len() {
+                    return None;
+                }
+                // Copies may overlap their own output
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // The 16x8 test images of tests/png: rows filtered with filters 0 to 4 in turn
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 8;
+
+    fn pixel(x: usize, y: usize) -> [u8; 3] {
+        [(x * 16) as u8, (y * 32) as u8, (x * y * 7 % 256) as u8]
+    }
+
+    fn expected_samples() -> Vec<f64> {
+        (0..HEIGHT)
+            .flat_map(|y| (0..WIDTH).flat_map(move |x| pixel(x, y)))
+            .map(|c| c as f64 / 255.0)
+            .collect()
+    }
+
+    fn fixture(name: &str) -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/png").join(name);
+        path.to_str().unwrap().to_string()
+    }
+
+    fn temp_file(name: &str) -> String {
+        let path = env::temp_dir().join(format!("rt-{}-{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn assert_decodes(name: &str) {
+        let png = read_png(&fixture(name)).unwrap();
+        assert_eq!((png.width, png.height), (WIDTH, HEIGHT), "size of {}", name);
+        for (i, (actual, expected)) in png.samples.iter().zip(expected_samples()).enumerate() {
+            assert!((actual - expected).abs() < 1.0e-9, "{}: sample {} is {}, not {}", name, i, actual, expected);
+        }
+        assert_eq!(png.samples.len(), 3 * WIDTH * HEIGHT);
+    }
+
+    // PNG file with an RGB header of the given size and the given image data
+    fn png_file(width: u32, height: u32, compressed: &[u8]) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut bytes = SIGNATURE.to_vec();
+        for (kind, data) in [(b"IHDR", &header[..]), (b"IDAT", compressed), (b"IEND", &[][..])] {
+            let mut chunk = kind.to_vec();
+            chunk.extend_from_slice(data);
+            bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&chunk);
+            bytes.extend_from_slice(&crc32(&chunk).to_be_bytes());
+        }
+        bytes
+    }
+
+    fn assert_invalid(bytes: &[u8], name: &str) {
+        let path = temp_file(name);
+        fs::write(&path, bytes).unwrap();
+        let result = read_png(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::InvalidFile { .. })), "{} was accepted", name);
+    }
+
+    #[test]
+    fn decode_stored_blocks() {
+        assert_decodes("stored.png");
+    }
+
+    #[test]
+    fn decode_fixed_huffman_codes() {
+        assert_decodes("fixed.png");
+    }
+
+    #[test]
+    fn decode_dynamic_huffman_codes() {
+        assert_decodes("dynamic.png");
+    }
+
+    #[test]
+    fn decode_16_bit_rgba_and_palette() {
+        assert_decodes("rgba16.png");
+        assert_decodes("palette.png");
+    }
+
+    #[test]
+    fn round_trip() {
+        let rgb: Vec<u8> = (0..HEIGHT).flat_map(|y| (0..WIDTH).flat_map(move |x| pixel(x, y))).collect();
+        let path = temp_file("round-trip.png");
+        write_png(&path, WIDTH, HEIGHT, &rgb).unwrap();
+        let png = read_png(&path);
+        fs::remove_file(&path).unwrap();
+
+        let png = png.unwrap();
+        assert_eq!((png.width, png.height), (WIDTH, HEIGHT));
+        let decoded: Vec<u8> = png.samples.iter().map(|s| (s * 255.0).round() as u8).collect();
+        assert_eq!(decoded, rgb);
+    }
+
+    #[test]
+    fn round_trip_over_several_stored_blocks() {
+        // 300 x 100 RGB rows take more than the 65535 bytes of a stored block
+        let (width, height) = (300, 100);
+        let rgb: Vec<u8> = (0..width * height * 3).map(|i| (i * 31 % 251) as u8).collect();
+        let path = temp_file("round-trip-blocks.png");
+        write_png(&path, width, height, &rgb).unwrap();
+        let png = read_png(&path);
+        fs::remove_file(&path).unwrap();
+
+        let decoded: Vec<u8> = png.unwrap().samples.iter().map(|s| (s * 255.0).round() as u8).collect();
+        assert_eq!(decoded, rgb);
+    }
+
+    #[test]
+    fn reject_oversized_and_empty_headers() {
+        let one_pixel = zlib_store(&[0, 1, 2, 3]);
+        assert_invalid(&png_file(u32::MAX, u32::MAX, &one_pixel), "huge.png");
+        assert_invalid(&png_file(0, 1, &one_pixel), "empty.png");
+    }
+
+    #[test]
+    fn reject_more_data_than_the_header_says() {
+        // Two rows of data for a one row image
+        let data = zlib_store(&[0, 1, 2, 3, 0, 4, 5, 6]);
+        assert_invalid(&png_file(1, 1, &data), "long.png");
+    }
+
+    #[test]
+    fn reject_truncated_data_and_bad_checksums() {
+        assert_invalid(&png_file(2, 1, &zlib_store(&[0, 1, 2, 3])), "truncated.png");
+
+        let mut bytes = png_file(1, 1, &zlib_store(&[0, 1, 2, 3]));
+        let last = bytes.len() - 20;
+        bytes[last] ^= 1;
+        assert_invalid(&bytes, "checksum.png");
+    }
+}
@@ -0,0 +1,177 @@
+// Real roots of low degree polynomials, after Jochen Schwarze's solvers
+// from Graphics Gems I. Coefficients are given from the constant term up:
+// c[0] + c[1] x + c[2] x^2 + ...
+
+const EPS: f64 = 1.0e-9;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPS
+}
+
+pub fn solve_quadratic(c: [f64; 3]) -> Vec<f64> {
+    // Normal form: x^2 + px + q = 0
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let d = p * p - q;
+
+    if is_zero(d) {
+        vec![-p]
+    } else if d < 0.0 {
+        Vec::new()
+    } else {
+        let sqrt_d = f64::sqrt(d);
+        vec![sqrt_d - p, -sqrt_d - p]
+    }
+}
+
+pub fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    // Normal form: x^3 + Ax^2 + Bx + C = 0
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let cc = c[0] / c[3];
+
+    // Substitute x = y - A/3 to eliminate the quadric term: y^3 + 3py + 2q = 0
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + cc) / 2.0;
+
+    let cb_p = p * p * p;
+    let d = q * q + cb_p;
+
+    let mut roots = if is_zero(d) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = f64::cbrt(-q);
+            vec![2.0 * u, -u]
+        }
+    } else if d < 0.0 {
+        // Casus irreducibilis: three real solutions
+        let phi = f64::acos(-q / f64::sqrt(-cb_p)) / 3.0;
+        let t = 2.0 * f64::sqrt(-p);
+        vec![
+            t * f64::cos(phi),
+            -t * f64::cos(phi + std::f64::consts::PI / 3.0),
+            -t * f64::cos(phi - std::f64::consts::PI / 3.0),
+        ]
+    } else {
+        let sqrt_d = f64::sqrt(d);
+        vec![f64::cbrt(sqrt_d - q) - f64::cbrt(sqrt_d + q)]
+    };
+
+    for root in &mut roots {
+        *root -= a / 3.0;
+    }
+    roots
+}
+
+pub fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    // Normal form: x^4 + Ax^3 + Bx^2 + Cx + D = 0
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let cc = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    // Substitute x = y - A/4 to eliminate the cubic term: y^4 + py^2 + qy + r = 0
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * cc / 4.0 + d;
+
+    let mut roots = if is_zero(r) {
+        // No absolute term: y(y^3 + py + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // Solve the resolvent cubic and use one of its roots to build two quadratics
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+
+        let mut u = z * z - r;
+        let mut v = 2.0 * z - p;
+        if is_zero(u) {
+            u = 0.0;
+        } else if u > 0.0 {
+            u = f64::sqrt(u);
+        } else {
+            return Vec::new();
+        }
+        if is_zero(v) {
+            v = 0.0;
+        } else if v > 0.0 {
+            v = f64::sqrt(v);
+        } else {
+            return Vec::new();
+        }
+
+        let mut roots = solve_quadratic([z - u, if q < 0.0 { -v } else { v }, 1.0]);
+        roots.extend(solve_quadratic([z + u, if q < 0.0 { v } else { -v }, 1.0]));
+        roots
+    };
+
+    for root in &mut roots {
+        *root -= a / 4.0;
+        *root = polish_root(&c, *root);
+    }
+    roots
+}
+
+// A couple of Newton iterations to recover the precision lost in the closed form
+fn polish_root(c: &[f64; 5], mut x: f64) -> f64 {
+    for _ in 0..2 {
+        let f = (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+        let df = ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+        if df.abs() < EPS {
+            break;
+        }
+        x -= f / df;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sorted roots, each within 1e-6 of one of `expected`, and each of
+    // `expected` found
+    fn assert_roots(c: [f64; 5], expected: &[f64]) {
+        let mut roots = solve_quartic(c);
+        roots.sort_by(|a, b| a.total_cmp(b));
+        for root in &roots {
+            assert!(expected.iter().any(|x| (root - x).abs() < 1.0e-6), "unexpected root {} in {:?}", root, roots);
+        }
+        for x in expected {
+            assert!(roots.iter().any(|root| (root - x).abs() < 1.0e-6), "root {} missing from {:?}", x, roots);
+        }
+    }
+
+    #[test]
+    fn four_real_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots([24.0, -50.0, 35.0, -10.0, 1.0], &[1.0, 2.0, 3.0, 4.0]);
+        // (x + 1.5)(x - 0.5)(x - 2)(x - 5), with a leading coefficient of 2
+        let c = [-7.5, 15.25, 2.25, -6.0, 1.0].map(|c| 2.0 * c);
+        assert_roots(c, &[-1.5, 0.5, 2.0, 5.0]);
+    }
+
+    #[test]
+    fn double_root() {
+        // (x - 1)^2 (x - 2)(x - 3)
+        assert_roots([6.0, -17.0, 17.0, -7.0, 1.0], &[1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn two_real_roots() {
+        // (x^2 + 1)(x - 1)(x + 2)
+        assert_roots([-2.0, 1.0, -1.0, 1.0, 1.0], &[-2.0, 1.0]);
+    }
+
+    #[test]
+    fn no_real_root() {
+        // (x^2 + 1)(x^2 + 4)
+        assert!(solve_quartic([4.0, 0.0, 5.0, 0.0, 1.0]).is_empty());
+        // (x^2 - 2x + 2)(x^2 + 2x + 5)
+        assert!(solve_quartic([10.0, -6.0, 3.0, 0.0, 1.0]).is_empty());
+    }
+}
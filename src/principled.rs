@@ -0,0 +1,396 @@
+// Principled (Disney-style) uber material.
+//
+// The BSDF is the sum of four lobes: a Burley diffuse lobe with sheen, a GGX
+// specular reflection lobe, a GGX clearcoat lobe and a GGX transmission lobe.
+// `scatter` picks one lobe to sample a direction and then weights it with the
+// full BSDF divided by the combined density of all lobes (one-sample MIS), so
+// the result stays unbiased whatever lobe produced the direction.
+//
+// Seen from inside an object with transmission, the surface is the same mix:
+// the transmitted fraction is the dielectric interface, which reflects or lets
+// the light out, and the rest is the opaque base (diffuse and metal), which
+// reflects the light back inside. Clearcoat and sheen only coat the outside.
+
+use crate::color::Color;
+use crate::common;
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::microfacet;
+use crate::ray::Ray;
+use crate::vec3::{self, Vec3};
+
+// Clearcoat is a fixed, almost smooth varnish layer with an IOR of 1.5
+const CLEARCOAT_ALPHA: f64 = 0.05;
+const CLEARCOAT_F0: f64 = 0.04;
+
+pub struct Principled {
+    pub base_color: Color,
+    pub metallic: f64,
+    pub roughness: f64,
+    pub specular: f64,
+    pub clearcoat: f64,
+    pub sheen: f64,
+    pub transmission: f64,
+    pub ior: f64,
+    pub emission: Color,
+}
+
+impl Principled {
+    // A plain dielectric with the given color; the other parameters can be set
+    // with struct update syntax, e.g. `Principled { metallic: 1.0, ..Principled::new(c) }`
+    pub fn new(base_color: Color) -> Principled {
+        Principled {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            clearcoat: 0.0,
+            sheen: 0.0,
+            transmission: 0.0,
+            ior: 1.5,
+            emission: Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn alpha(&self) -> f64 {
+        microfacet::roughness_to_alpha(self.roughness, 0.0).0
+    }
+
+    fn diffuse_weight(&self) -> f64 {
+        (1.0 - self.metallic) * (1.0 - self.transmission)
+    }
+
+    fn transmission_weight(&self) -> f64 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    // Color of the light crossing the surface. It is applied on the way in and
+    // on the way out, so light going through the object gets `base_color` once.
+    fn transmission_tint(&self) -> Color {
+        let c = self.base_color;
+        Color::new(c.x().sqrt(), c.y().sqrt(), c.z().sqrt())
+    }
+
+    // Opaque part of the material, without transmission and outer layers, as
+    // seen from inside the object. Its metallic parameter is renormalized so it
+    // keeps the proportion of metal and diffuse of the whole material.
+    fn opaque_base(&self) -> Principled {
+        let opaque = 1.0 - self.transmission_weight();
+        Principled {
+            metallic: if opaque > 0.0 { self.metallic / opaque } else { 0.0 },
+            clearcoat: 0.0,
+            sheen: 0.0,
+            transmission: 0.0,
+            emission: Color::default(),
+            ..*self
+        }
+    }
+
+    // Specular color at normal incidence
+    fn specular_f0(&self) -> Color {
+        let dielectric = 0.08 * self.specular * Color::new(1.0, 1.0, 1.0);
+        lerp(dielectric, self.base_color, self.metallic)
+    }
+
+    // Probabilities of sampling the diffuse, specular, clearcoat and transmission lobes
+    fn lobe_probabilities(&self) -> [f64; 4] {
+        let weights = [
+            self.diffuse_weight() * luminance(self.base_color).max(0.01),
+            luminance(self.specular_f0()).max(0.05),
+            0.25 * self.clearcoat,
+            self.transmission_weight() * luminance(self.base_color).max(0.01),
+        ];
+        let total: f64 = weights.iter().sum();
+        weights.map(|w| w / total)
+    }
+
+    // BSDF times the cosine of the incoming direction, both directions in the local frame
+    fn eval(&self, wo: Vec3, wi: Vec3, eta: f64) -> Color {
+        if wi.z() > 0.0 {
+            self.eval_reflection(wo, wi)
+        } else if wi.z() < 0.0 {
+            self.eval_transmission(wo, wi, eta)
+        } else {
+            Color::default()
+        }
+    }
+
+    fn eval_reflection(&self, wo: Vec3, wi: Vec3) -> Color {
+        let h = vec3::unit_vector(wo + wi);
+        let cos_o = wo.z();
+        let cos_i = wi.z();
+        let cos_d = vec3::dot(wi, h);
+
+        // Burley diffuse with retro-reflection, plus sheen at grazing angles
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let fd = (1.0 + (fd90 - 1.0) * schlick_weight(cos_i)) * (1.0 + (fd90 - 1.0) * schlick_weight(cos_o));
+        let diffuse = self.diffuse_weight() / common::PI * fd * self.base_color;
+        let sheen_color = lerp(Color::new(1.0, 1.0, 1.0), tint(self.base_color), 0.5);
+        let sheen = (1.0 - self.metallic) * self.sheen * schlick_weight(cos_d) * sheen_color;
+
+        let alpha = self.alpha();
+        let specular = microfacet::ggx_d(h, alpha, alpha) * microfacet::smith_g2(wo, wi, alpha, alpha)
+            / (4.0 * cos_o * cos_i)
+            * schlick(self.specular_f0(), cos_d);
+
+        let clearcoat = 0.25 * self.clearcoat
+            * microfacet::ggx_d(h, CLEARCOAT_ALPHA, CLEARCOAT_ALPHA)
+            * microfacet::smith_g2(wo, wi, CLEARCOAT_ALPHA, CLEARCOAT_ALPHA)
+            / (4.0 * cos_o * cos_i)
+            * (CLEARCOAT_F0 + (1.0 - CLEARCOAT_F0) * schlick_weight(cos_d));
+
+        cos_i * (diffuse + sheen + specular + clearcoat * Color::new(1.0, 1.0, 1.0))
+    }
+
+    // Walter et al. 2007 rough transmission, `eta` being the incident over transmitted IOR
+    fn eval_transmission(&self, wo: Vec3, wi: Vec3, eta: f64) -> Color {
+        let weight = self.transmission_weight();
+        if weight == 0.0 {
+            return Color::default();
+        }
+        let Some(h) = transmission_half_vector(wo, wi, eta) else {
+            return Color::default();
+        };
+
+        let oh = vec3::dot(wo, h);
+        let ih = vec3::dot(wi, h);
+        let denom = (eta * oh + ih).powi(2);
+        let alpha = self.alpha();
+        let fresnel = microfacet::fresnel_dielectric(oh, eta);
+
+        let f = (oh * ih).abs() / (wo.z() * wi.z().abs()) * (1.0 - fresnel)
+            * microfacet::ggx_d(h, alpha, alpha)
+            * microfacet::smith_g2(wo, Vec3::new(wi.x(), wi.y(), -wi.z()), alpha, alpha)
+            / denom;
+        weight * f * wi.z().abs() * self.transmission_tint()
+    }
+
+    fn pdf(&self, wo: Vec3, wi: Vec3, eta: f64) -> f64 {
+        let [p_diffuse, p_specular, p_clearcoat, p_transmission] = self.lobe_probabilities();
+        let alpha = self.alpha();
+
+        if wi.z() > 0.0 {
+            let h = vec3::unit_vector(wo + wi);
+            let jacobian = 1.0 / (4.0 * vec3::dot(wo, h));
+            p_diffuse * wi.z() / common::PI
+                + p_specular * microfacet::vndf_pdf(wo, h, alpha, alpha) * jacobian
+                + p_clearcoat * microfacet::vndf_pdf(wo, h, CLEARCOAT_ALPHA, CLEARCOAT_ALPHA) * jacobian
+        } else {
+            let Some(h) = transmission_half_vector(wo, wi, eta) else {
+                return 0.0;
+            };
+            let ih = vec3::dot(wi, h);
+            let jacobian = ih.abs() / (eta * vec3::dot(wo, h) + ih).powi(2);
+            p_transmission * microfacet::vndf_pdf(wo, h, alpha, alpha) * jacobian
+        }
+    }
+
+    // Sample a direction from one lobe chosen according to `lobe_probabilities`
+    fn sample(&self, wo: Vec3, eta: f64) -> Option<Vec3> {
+        let [p_diffuse, p_specular, p_clearcoat, _] = self.lobe_probabilities();
+        let alpha = self.alpha();
+        let xi = common::random_double();
+
+        if xi < p_diffuse {
+            // Cosine weighted hemisphere
+            let wi = Vec3::new(0.0, 0.0, 1.0) + vec3::random_unit_vector();
+            if wi.near_zero() {
+                return Some(Vec3::new(0.0, 0.0, 1.0));
+            }
+            Some(vec3::unit_vector(wi))
+        } else if xi < p_diffuse + p_specular {
+            let h = microfacet::sample_vndf(wo, alpha, alpha);
+            Some(vec3::reflect(-wo, h))
+        } else if xi < p_diffuse + p_specular + p_clearcoat {
+            let h = microfacet::sample_vndf(wo, CLEARCOAT_ALPHA, CLEARCOAT_ALPHA);
+            Some(vec3::reflect(-wo, h))
+        } else {
+            let h = microfacet::sample_vndf(wo, alpha, alpha);
+            let cos_i = vec3::dot(wo, h);
+            if eta * eta * (1.0 - cos_i * cos_i) >= 1.0 {
+                return None;
+            }
+            Some(vec3::refract(-wo, h, eta))
+        }
+    }
+
+    // Direction and weight of a scattering from the outside, sampled with
+    // `sample` and weighted with the whole BSDF over its density
+    fn scatter_from_outside(&self, wo: Vec3) -> Option<(Vec3, Color)> {
+        let eta = 1.0 / self.ior;
+        let wi = self.sample(wo, eta)?;
+        let pdf = self.pdf(wo, wi, eta);
+        if wi.z() == 0.0 || pdf <= 0.0 {
+            return None;
+        }
+        Some((wi, self.eval(wo, wi, eta) / pdf))
+    }
+
+    // Inside the object, the transmitted fraction of the surface is the rough
+    // dielectric interface: reflect or refract out according to Fresnel, like
+    // `RoughDielectric`, tinted when crossing. The rest is the opaque base.
+    // Each part is picked with its own weight, which cancels out.
+    fn scatter_from_inside(&self, wo: Vec3) -> Option<(Vec3, Color)> {
+        if common::random_double() >= self.transmission_weight() {
+            return self.opaque_base().scatter_from_outside(wo);
+        }
+
+        let alpha = self.alpha();
+        let h = microfacet::sample_vndf(wo, alpha, alpha);
+        let cos_i = vec3::dot(wo, h);
+        let (wi, tint) = if microfacet::fresnel_dielectric(cos_i, self.ior) > common::random_double() {
+            (vec3::reflect(-wo, h), Color::new(1.0, 1.0, 1.0))
+        } else {
+            (vec3::refract(-wo, h, self.ior), self.transmission_tint())
+        };
+        let g = microfacet::smith_g2(wo, Vec3::new(wi.x(), wi.y(), wi.z().abs()), alpha, alpha)
+            / microfacet::smith_g1(wo, alpha, alpha);
+        Some((wi, g * tint))
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        rec: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let wo = microfacet::to_local(rec, -vec3::unit_vector(r_in.direction()));
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        let scattering = if !rec.front_face && self.transmission_weight() > 0.0 {
+            self.scatter_from_inside(wo)
+        } else {
+            self.scatter_from_outside(wo)
+        };
+        let Some((wi, weight)) = scattering else {
+            return false;
+        };
+
+        *attenuation = weight;
+        *scattered = Ray::new(rec.p, microfacet::to_world(rec, wi), r_in.time());
+        true
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        if rec.front_face {
+            self.emission
+        } else {
+            Color::default()
+        }
+    }
+}
+
+// Generalized half vector of a refraction event, oriented towards the outside
+fn transmission_half_vector(wo: Vec3, wi: Vec3, eta: f64) -> Option<Vec3> {
+    let h = -(eta * wo + wi);
+    if h.near_zero() {
+        return None;
+    }
+    let h = vec3::unit_vector(h);
+    let h = if h.z() < 0.0 { -h } else { h };
+    if vec3::dot(wo, h) <= 0.0 || vec3::dot(wi, h) >= 0.0 {
+        return None;
+    }
+    Some(h)
+}
+
+fn luminance(c: Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+// Base color normalized to unit luminance, used to tint the sheen
+fn tint(c: Color) -> Color {
+    let lum = luminance(c);
+    if lum > 0.0 {
+        c / lum
+    } else {
+        Color::new(1.0, 1.0, 1.0)
+    }
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    (1.0 - t) * a + t * b
+}
+
+fn schlick_weight(cos: f64) -> f64 {
+    (1.0 - common::clamp(cos, 0.0, 1.0)).powi(5)
+}
+
+fn schlick(f0: Color, cos: f64) -> Color {
+    f0 + schlick_weight(cos) * (Color::new(1.0, 1.0, 1.0) - f0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    // Scatter a ray leaving the object through a surface facing +z at normal
+    // incidence: the back face of the hit
+    fn scatter_from_inside(material: &Principled) -> Option<(Ray, Color)> {
+        let r_in = Ray::new(Point3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let mut rec = HitRecord::new();
+        rec.t = 1.0;
+        rec.p = r_in.at(1.0);
+        rec.set_face_normal(&r_in, Vec3::new(0.0, 0.0, 1.0));
+        rec.set_tangent_frame(Vec3::new(1.0, 0.0, 0.0));
+        assert!(!rec.front_face);
+
+        let mut attenuation = Color::default();
+        let mut scattered = Ray::default();
+        material
+            .scatter(&r_in, &rec, &mut attenuation, &mut scattered)
+            .then_some((scattered, attenuation))
+    }
+
+    #[test]
+    fn light_leaving_is_tinted() {
+        common::seed_rng(1);
+        let glass = Principled { roughness: 0.0, transmission: 1.0, ..Principled::new(Color::new(0.25, 1.0, 1.0)) };
+        let mut refracted = 0;
+        for _ in 0..200 {
+            let (scattered, attenuation) = scatter_from_inside(&glass).unwrap();
+            if scattered.direction().z() > 0.0 {
+                // Half of the tint, the other half was applied on the way in
+                assert!((attenuation.x() / attenuation.y() - 0.5).abs() < 1.0e-9);
+                refracted += 1;
+            } else {
+                assert!((attenuation.x() - attenuation.y()).abs() < 1.0e-9);
+            }
+        }
+        assert!(refracted > 150, "{} refractions out of 200", refracted);
+    }
+
+    #[test]
+    fn opaque_base_reflects_inside() {
+        common::seed_rng(2);
+        // Half metal, half glass
+        let material = Principled {
+            metallic: 0.5,
+            roughness: 0.2,
+            transmission: 1.0,
+            ..Principled::new(Color::new(1.0, 0.5, 0.0))
+        };
+        let (mut reflected, mut total) = (0, Color::default());
+        let count = 2000;
+        for _ in 0..count {
+            let Some((scattered, attenuation)) = scatter_from_inside(&material) else {
+                continue;
+            };
+            assert!(attenuation.x().is_finite() && attenuation.y().is_finite() && attenuation.z().is_finite());
+            if scattered.direction().z() < 0.0 {
+                reflected += 1;
+            }
+            total += attenuation;
+        }
+        // The metal half reflects everything back inside, with its color
+        assert!(reflected > count * 2 / 5, "{} reflections out of {}", reflected, count);
+        let mean = total / count as f64;
+        assert!(mean.x() > mean.z() + 0.2 && mean.x() < 1.05, "mean weight ({})", mean);
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// How far a render is, reported to the progress callback of the renderer
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    // Part of the render done, from 0 to 1
+    pub fraction: f64,
+    pub elapsed: Duration,
+    // Rays traced so far: camera rays, bounces and shadow rays
+    pub rays: u64,
+}
+
+impl Progress {
+    pub fn percent(&self) -> f64 {
+        100.0 * self.fraction
+    }
+
+    // Time left at the speed so far, unknown until some of the render is done
+    pub fn eta(&self) -> Option<Duration> {
+        if self.fraction <= 0.0 {
+            return None;
+        }
+        Some(self.elapsed.mul_f64((1.0 - self.fraction).max(0.0) / self.fraction))
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.rays as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+// Flag shared with a render to stop it early. Clones share the flag, and it
+// can be set from another thread, e.g. by the user interface.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -1,8 +1,10 @@
 // rectangle.rs
 
+use crate::aabb::{self, Aabb};
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::stats::{self, Primitive};
 use crate::vec3::{dot, Point3, Vec3};
 use std::rc::Rc;
 
@@ -27,6 +29,7 @@ impl Rectangle {
 
 impl Hittable for Rectangle {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Rectangle);
         // Calcul de la normale basée sur l'orientation dans le monde
         let normal = self.size_x.cross(&self.size_z).unit_vector();
 
@@ -47,11 +50,25 @@ impl Hittable for Rectangle {
                     rec.normal = normal;
                     rec.mat = Some(self.mat.clone());
                     rec.set_face_normal(r, normal);
+                    rec.u = u / self.size_x.length();
+                    rec.v = w / self.size_z.length();
+                    rec.set_tangent_frame(self.size_x);
                     return true;
                 }
             }
         }
         false
     }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        // Un rectangle est plat : on lui donne une petite épaisseur
+        let far = self.corner + self.size_x + self.size_z;
+        let corners = aabb::surrounding_box(
+            &Aabb::new(self.corner, far),
+            &Aabb::new(self.corner + self.size_x, self.corner + self.size_z),
+        );
+        *output_box = corners.padded(1.0e-4);
+        true
+    }
 }
 
@@ -0,0 +1,342 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::common;
+use crate::error::{Error, Result};
+use crate::film::{Film, Region};
+use crate::hittable::{HitRecord, Hittable};
+use crate::image::Image;
+use crate::light::Light;
+use crate::metrics;
+use crate::progress::{CancelToken, Progress};
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::stats::{self, RenderStats};
+use crate::vec3;
+
+// Function called with the progress of a render
+pub type ProgressCallback = Box<dyn Fn(&Progress)>;
+
+// What a debug render shows of the first surface hit by every camera ray,
+// instead of tracing paths. The materials and lights are ignored, except for
+// the material ID.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugMode {
+    // Normal facing the ray, each component mapped from [-1, 1] to [0, 1]
+    Normal,
+    // Texture coordinates, u in red and v in green
+    Uv,
+    // Distance from the camera, from white at the camera to black at `far`
+    Depth { far: f64 },
+    // Green where the ray hits the front of a surface, red where it hits the back
+    FrontFace,
+    // One color per material, in the order the render meets them
+    MaterialId,
+    // Intersection tests done for the camera ray, on the heatmap scale of
+    // the image comparison up to `max` tests
+    IntersectionTests { max: u64 },
+}
+
+// Path tracer configuration. The image is rendered in passes over the region
+// (the whole image by default), each pass adding one sample to every pixel.
+pub struct Renderer {
+    width: usize,
+    height: usize,
+    samples_per_pixel: u32,
+    max_depth: i32,
+    region: Option<Region>,
+    progress: Option<ProgressCallback>,
+    cancel: Option<CancelToken>,
+    debug_mode: Option<DebugMode>,
+}
+
+impl Renderer {
+    pub fn new(width: usize, height: usize) -> Renderer {
+        Renderer {
+            width,
+            height,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            region: None,
+            progress: None,
+            cancel: None,
+            debug_mode: None,
+        }
+    }
+
+    pub fn with_samples(mut self, samples_per_pixel: u32) -> Renderer {
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+
+    // Maximum number of bounces of a path
+    pub fn with_max_depth(mut self, max_depth: i32) -> Renderer {
+        self.max_depth = max_depth;
+        self
+    }
+
+    // Render only `region` of the image
+    pub fn with_region(mut self, region: Region) -> Renderer {
+        self.region = Some(region);
+        self
+    }
+
+    // Call `callback` as the render goes, after every row of every pass
+    pub fn with_progress(mut self, callback: impl Fn(&Progress) + 'static) -> Renderer {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    // Stop the render with Error::Cancelled once `token` is cancelled. Only
+    // whole passes are added to the film, so it stays usable.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Renderer {
+        self.cancel = Some(token);
+        self
+    }
+
+    // Render `mode` instead of the scene lit by its materials
+    pub fn with_debug_mode(mut self, mode: DebugMode) -> Renderer {
+        self.debug_mode = Some(mode);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    pub fn region(&self) -> Region {
+        self.region.unwrap_or(Region {
+            x0: 0,
+            y0: 0,
+            x1: self.width,
+            y1: self.height,
+        })
+    }
+
+    // Render the region with all the samples and return its image
+    pub fn render(&self, scene: &Scene, camera: &dyn Camera) -> Result<Image> {
+        let mut film = Film::with_region(self.width, self.height, self.region())?;
+        self.render_film(scene, camera, &mut film, |_| Ok(()))?;
+        Ok(film.image())
+    }
+
+    // Add passes to `film` until it has all the samples per pixel, calling
+    // `after_pass` after each of them, e.g. to save previews. A film resumed
+    // from a checkpoint only gets the missing passes. The statistics time the
+    // rendering itself as the "render" phase, without `after_pass`.
+    pub fn render_film(
+        &self,
+        scene: &Scene,
+        camera: &dyn Camera,
+        film: &mut Film,
+        mut after_pass: impl FnMut(&Film) -> Result<()>,
+    ) -> Result<RenderStats> {
+        let start = Instant::now();
+        let mut after_pass_time = Duration::ZERO;
+        let region = film.region();
+        let first_pass = film.passes();
+        let rows = (self.samples_per_pixel.saturating_sub(first_pass) as usize * region.height()).max(1);
+        let mut stats = RenderStats::default();
+        stats.start();
+        let mut samples = vec![Color::default(); region.width() * region.height()];
+        let mut materials = Vec::new();
+
+        for pass in first_pass..self.samples_per_pixel {
+            for (k, row) in (region.y0..region.y1).enumerate() {
+                if self.cancel.as_ref().is_some_and(|token| token.is_cancelled()) {
+                    return Err(Error::Cancelled);
+                }
+
+                // Rows of the camera count from the bottom of the image
+                let j = self.height - 1 - row;
+                for i in region.x0..region.x1 {
+                    let u = (i as f64 + common::random_double()) / (self.width - 1) as f64;
+                    let v = (j as f64 + common::random_double()) / (self.height - 1) as f64;
+                    samples[k * region.width() + i - region.x0] = match camera.get_weighted_ray(u, v) {
+                        Some((r, weight)) => {
+                            stats.camera_rays += 1;
+                            match self.debug_mode {
+                                Some(mode) => debug_color(mode, &r, scene.objects(), &mut materials)?,
+                                None => weight * ray_color(&r, scene.objects(), self.max_depth, scene.lights(), &mut stats)?,
+                            }
+                        }
+                        None => Color::default(),
+                    };
+                }
+
+                if let Some(callback) = &self.progress {
+                    let rows_done = (pass - first_pass) as usize * region.height() + k + 1;
+                    callback(&Progress {
+                        fraction: rows_done as f64 / rows as f64,
+                        elapsed: start.elapsed(),
+                        rays: stats.total_rays(),
+                    });
+                }
+            }
+            film.add_pass(&samples);
+            let after_pass_start = Instant::now();
+            after_pass(film)?;
+            after_pass_time += after_pass_start.elapsed();
+        }
+
+        stats.stop();
+        stats.phases.push(("render", start.elapsed().saturating_sub(after_pass_time)));
+        Ok(stats)
+    }
+}
+
+// Color seen along `r`, counting the shadow and scattered rays in `stats`
+fn ray_color(r: &Ray, world: &dyn Hittable, depth: i32, lights: &[Light], stats: &mut RenderStats) -> Result<Color> {
+    if depth <= 0 {
+        return Ok(Color::new(0.0, 0.0, 0.0));
+    }
+
+    let mut rec = HitRecord::new();
+    if world.hit(r, 0.001, common::INFINITY, &mut rec) {
+        let mat = rec.mat.clone().ok_or(Error::MissingMaterial)?;
+        // Normal and bump maps perturb the normal before lighting and scattering
+        rec.normal = mat.shading_normal(&rec);
+        rec.set_tangent_frame(rec.tangent);
+
+        let mut total_light = Color::new(0.0, 0.0, 0.0);
+
+        for light in lights {
+            let light_dir = light.direction_to_light(&rec.p);
+            let light_intensity = light.get_intensity(light_dir.length());
+
+            // Vérification des ombres pour chaque lumière
+            let shadow_ray = Ray::new(rec.p, light_dir, r.time());
+            stats.shadow_rays += 1;
+            let in_shadow = world.hit(&shadow_ray, 0.001, common::INFINITY, &mut HitRecord::new());
+            if !in_shadow {
+                // Media scatter the light by their phase function, surfaces by the
+                // cosine to their normal
+                let weight = mat.phase(r, light_dir).unwrap_or_else(|| rec.normal.dot(&light_dir).max(0.0));
+                total_light += light.color * (weight * light_intensity);
+            }
+        }
+
+        let emitted = mat.emitted(&rec);
+        let mut scattered = Ray::default();
+        let mut attenuation = Color::default();
+        if mat.scatter(r, &rec, &mut attenuation, &mut scattered) {
+            // The last bounce is not traced
+            if depth > 1 {
+                stats.secondary_rays += 1;
+            }
+            return Ok(emitted + attenuation * ray_color(&scattered, world, depth - 1, lights, stats)? + total_light);
+        }
+        return Ok(emitted + total_light);
+    }
+
+    let unit_direction = vec3::unit_vector(r.direction());
+    let t = 0.5 * (unit_direction.y() + 1.0);
+    Ok((1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0))
+}
+
+// Color of the debug `mode` along `r`. `materials` lists the materials met so
+// far, whose positions are their IDs.
+fn debug_color(mode: DebugMode, r: &Ray, world: &dyn Hittable, materials: &mut Vec<*const ()>) -> Result<Color> {
+    let tests_before = stats::total_intersection_tests();
+    let mut rec = HitRecord::new();
+    let hit = world.hit(r, 0.001, common::INFINITY, &mut rec);
+    let tests = stats::total_intersection_tests() - tests_before;
+
+    // Displayed colors
+    let c = match mode {
+        DebugMode::IntersectionTests { max } => metrics::false_color(tests as f64 / max.max(1) as f64),
+        _ if !hit => Color::new(0.0, 0.0, 0.0),
+        DebugMode::Normal => 0.5 * (rec.normal + Color::new(1.0, 1.0, 1.0)),
+        DebugMode::Uv => Color::new(rec.u, rec.v, 0.0),
+        DebugMode::Depth { far } => {
+            let gray = 1.0 - common::clamp(rec.t * r.direction().length() / far, 0.0, 1.0);
+            Color::new(gray, gray, gray)
+        }
+        DebugMode::FrontFace => {
+            if rec.front_face {
+                Color::new(0.0, 1.0, 0.0)
+            } else {
+                Color::new(1.0, 0.0, 0.0)
+            }
+        }
+        DebugMode::MaterialId => {
+            let mat = rec.mat.as_ref().ok_or(Error::MissingMaterial)?;
+            let key = Rc::as_ptr(mat) as *const ();
+            let id = match materials.iter().position(|&m| m == key) {
+                Some(id) => id,
+                None => {
+                    materials.push(key);
+                    materials.len() - 1
+                }
+            };
+            material_color(id)
+        }
+    };
+    // Back to linear values, which the film gamma corrects
+    Ok(c * c)
+}
+
+// Bright color of material `id`, the hues of successive IDs a golden angle apart
+fn material_color(id: usize) -> Color {
+    let hue = (id as f64 * 0.618_033_988_749_895).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    // Saturation 0.7
+    0.3 * Color::new(1.0, 1.0, 1.0) + 0.7 * Color::new(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant_medium::ConstantMedium;
+    use crate::material::{HenyeyGreenstein, Isotropic, Material};
+    use crate::sphere::Sphere;
+    use crate::testing::assert_vec_close;
+    use crate::vec3::{Point3, Vec3};
+
+    // Direct light scattered back to a ray entering a medium lit from -x, which
+    // is so dense that the ray scatters where it enters
+    fn direct_light_from_minus_x(phase_function: Rc<dyn Material>) -> Color {
+        let medium = ConstantMedium::new(
+            Box::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, phase_function.clone())),
+            1.0e6,
+            phase_function,
+        );
+        let light = Light::new(Point3::new(-10.0, 0.0, 0.0), 1.0, Color::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        // A depth of 1 leaves out the scattered ray
+        ray_color(&r, &medium, 1, &[light], &mut RenderStats::default()).unwrap()
+    }
+
+    #[test]
+    fn isotropic_medium_lit_from_minus_x() {
+        let color = direct_light_from_minus_x(Rc::new(Isotropic::new(Color::new(0.8, 0.8, 0.8))));
+        let expected = 1.0 / (4.0 * common::PI);
+        assert_vec_close(color, Color::new(expected, expected, expected));
+    }
+
+    #[test]
+    fn forward_scattering_medium_lit_from_behind_the_camera() {
+        let color = direct_light_from_minus_x(Rc::new(HenyeyGreenstein::new(Color::new(0.8, 0.8, 0.8), 0.5)));
+        // Light turned back towards the camera, at an angle whose cosine is -1
+        let expected = 0.75 / (4.0 * common::PI * 2.25 * 1.5);
+        assert_vec_close(color, Color::new(expected, expected, expected));
+    }
+}
@@ -0,0 +1,31 @@
+use crate::hittable_list::HittableList;
+use crate::light::Light;
+
+// Everything rendered besides the camera: the objects and the point lights
+// shining on them
+pub struct Scene {
+    objects: HittableList,
+    lights: Vec<Light>,
+}
+
+impl Scene {
+    pub fn new(objects: HittableList) -> Scene {
+        Scene {
+            objects,
+            lights: Vec::new(),
+        }
+    }
+
+    pub fn with_light(mut self, light: Light) -> Scene {
+        self.lights.push(light);
+        self
+    }
+
+    pub fn objects(&self) -> &HittableList {
+        &self.objects
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+}
@@ -0,0 +1,587 @@
+use std::rc::Rc;
+
+use crate::capsule::Capsule;
+use crate::color::Color;
+use crate::cone::Cone;
+use crate::constant_medium::{ConstantMedium, GridMedium, VoxelGrid};
+use crate::csg::Csg;
+use crate::cube::Cube;
+use crate::cylindre::Cylinder;
+use crate::disk::Disk;
+use crate::ellipsoid::Ellipsoid;
+use crate::error::{Error, Result};
+use crate::hittable_list::HittableList;
+use crate::light::Light;
+use crate::material::{
+    Conductor, Dielectric, HenyeyGreenstein, Isotropic, Lambertian, Metal, NormalMapped, RoughDielectric,
+};
+use crate::moving_sphere::MovingSphere;
+use crate::normal_map::{BumpMap, NormalMap};
+use crate::plane::Plane;
+use crate::principled::Principled;
+use crate::rectangle::Rectangle;
+use crate::scene::Scene;
+use crate::sdf::{Sdf, SdfShape};
+use crate::sphere::Sphere;
+use crate::texture::{CheckerTexture, ImageTexture};
+use crate::torus::Torus;
+use crate::transform::{Placement, Transform};
+use crate::vec3::{Point3, Vec3};
+
+// Fonctions de création des scènes
+fn create_ground_scene() -> HittableList {
+    let mut world = HittableList::new();
+    let ground_material = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Box::new(Plane::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground_material,
+    )));
+    world
+}
+
+// Sol en damier, une case par unité
+fn create_checker_ground_scene() -> HittableList {
+    let mut world = HittableList::new();
+    let checker = Rc::new(CheckerTexture::new(
+        1.0,
+        Color::new(0.6, 0.6, 0.6),
+        Color::new(0.3, 0.3, 0.3),
+    ));
+    world.add(Box::new(Plane::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Rc::new(Lambertian::textured(checker)),
+    )));
+    world
+}
+
+fn create_cube_scene() -> HittableList {
+    let mut world = create_ground_scene();
+    let cube_material = Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1));
+    let cube = Cube::new(
+        Point3::new(-1.0, 0.0, -1.0),
+        Point3::new(1.0, 2.0, 1.0),
+        cube_material,
+    );
+    world.add(Box::new(cube));
+    world
+}
+
+fn create_cylinder_scene() -> HittableList {
+    let mut world = create_ground_scene();
+    let cylinder_material = Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    let cylinder = Cylinder::new(
+        Point3::new(-4.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.8,
+        4.0,
+        cylinder_material,
+    );
+    world.add(Box::new(cylinder));
+    world
+}
+
+fn create_sphere_scene() -> HittableList {
+    let mut world = create_ground_scene();
+    let material3 = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.2));
+    world.add(Box::new(Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        material3,
+    )));
+    world
+}
+
+fn create_plane_scene() -> HittableList {
+    let mut world = create_ground_scene();
+    // let plane_material = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.8));
+    let plane_material = Rc::new(Dielectric::new(0.3));
+    let rect = Rectangle::new(
+        Point3::new(0.0, 0.1, -2.0),  // Positionnement plus bas
+        Vec3::new(2.0, 0.0, 0.0),        // Largeur
+        Vec3::new(0.0, 0.0, -2.0),        // Profondeur
+        plane_material,
+    );
+    world.add(Box::new(rect));
+    world
+}
+
+fn create_bumpy_scene() -> Result<HittableList> {
+    let mut world = create_ground_scene();
+
+    // Sphère avec une normal map en espace tangent
+    let tiles = Rc::new(ImageTexture::load("textures/tiles_normal.ppm")?);
+    let sphere_material = Rc::new(NormalMapped::new(
+        Rc::new(Lambertian::new(Color::new(0.6, 0.4, 0.3))),
+        Rc::new(NormalMap::new(tiles, 1.0)),
+    ));
+    world.add(Box::new(Sphere::new(
+        Point3::new(3.0, 1.5, 0.0),
+        1.5,
+        sphere_material,
+    )));
+
+    // Cube avec une bump map en damier
+    let checker = Rc::new(CheckerTexture::new(
+        4.0,
+        Color::new(1.0, 1.0, 1.0),
+        Color::new(0.0, 0.0, 0.0),
+    ));
+    let cube_material = Rc::new(NormalMapped::new(
+        Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1)),
+        Rc::new(BumpMap::new(checker, 0.02)),
+    ));
+    world.add(Box::new(Cube::new(
+        Point3::new(-3.0, 0.0, -1.0),
+        Point3::new(-1.0, 2.0, 1.0),
+        cube_material,
+    )));
+
+    Ok(world)
+}
+
+fn create_metals_scene() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Conducteurs GGX : or, cuivre, aluminium et argent, du plus lisse au plus rugueux
+    let metals: [(Conductor, f64); 4] = [
+        (Conductor::gold(0.1, 0.0), -4.5),
+        (Conductor::copper(0.3, 0.0), -1.5),
+        (Conductor::aluminium(0.5, 0.8), 1.5),
+        (Conductor::silver(0.2, 0.0), 4.5),
+    ];
+    for (metal, x) in metals {
+        world.add(Box::new(Sphere::new(
+            Point3::new(x, 1.2, 0.0),
+            1.2,
+            Rc::new(metal),
+        )));
+    }
+
+    world
+}
+
+fn create_glass_scene() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Verre dépoli incolore
+    let frosted = Rc::new(RoughDielectric::new(1.5, 0.3, Color::new(0.0, 0.0, 0.0)));
+    world.add(Box::new(Sphere::new(
+        Point3::new(4.0, 1.2, 0.0),
+        1.2,
+        frosted,
+    )));
+
+    // Deux plaques du même verre teinté : la plus épaisse est plus colorée
+    let tinted = Rc::new(RoughDielectric::tinted(1.5, 0.0, Color::new(0.9, 0.6, 0.3)));
+    world.add(Box::new(Cube::new(
+        Point3::new(-0.5, 0.0, -0.2),
+        Point3::new(1.5, 2.5, 0.0),
+        tinted.clone(),
+    )));
+    world.add(Box::new(Cube::new(
+        Point3::new(-3.5, 0.0, -1.0),
+        Point3::new(-1.5, 2.5, 1.0),
+        tinted,
+    )));
+
+    world
+}
+
+fn create_principled_scene() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Une rangée de sphères utilisant le même matériau "principled"
+    let base = Color::new(0.8, 0.2, 0.1);
+    let materials = [
+        Principled { roughness: 0.8, sheen: 1.0, ..Principled::new(base) },
+        Principled { roughness: 0.4, clearcoat: 1.0, ..Principled::new(base) },
+        Principled { metallic: 1.0, roughness: 0.3, ..Principled::new(Color::new(0.9, 0.7, 0.3)) },
+        Principled { roughness: 0.1, transmission: 1.0, ..Principled::new(Color::new(0.9, 0.95, 1.0)) },
+        Principled { emission: Color::new(4.0, 3.0, 1.5), ..Principled::new(Color::new(0.0, 0.0, 0.0)) },
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Box::new(Sphere::new(
+            Point3::new(5.0 - 2.5 * i as f64, 1.0, 0.0),
+            1.0,
+            Rc::new(material),
+        )));
+    }
+
+    world
+}
+
+fn create_fog_scene() -> Result<HittableList> {
+    let mut world = create_ground_scene();
+
+    // Fumée sombre et homogène dans une sphère
+    let smoke_boundary = Sphere::new(
+        Point3::new(4.0, 1.5, 0.0),
+        1.5,
+        Rc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
+    );
+    world.add(Box::new(ConstantMedium::new(
+        Box::new(smoke_boundary),
+        0.8,
+        Rc::new(Isotropic::new(Color::new(0.2, 0.2, 0.2))),
+    )));
+
+    // Nuage hétérogène chargé depuis une grille de voxels
+    let cloud_min = Point3::new(-5.0, 0.0, -2.0);
+    let cloud_max = Point3::new(0.0, 4.0, 2.0);
+    let grid = VoxelGrid::load_raw("volumes/cloud.raw", 32, 32, 32, cloud_min, cloud_max)?;
+    let cloud_boundary = Cube::new(
+        cloud_min,
+        cloud_max,
+        Rc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
+    );
+    world.add(Box::new(GridMedium::new(
+        Box::new(cloud_boundary),
+        grid,
+        12.0,
+        Rc::new(HenyeyGreenstein::new(Color::new(0.9, 0.9, 0.9), 0.6)),
+    )));
+
+    Ok(world)
+}
+
+fn create_csg_scene() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Cube percé d'un trou cylindrique
+    let cube_material = Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1));
+    let drill_material = Rc::new(Lambertian::new(Color::new(0.9, 0.9, 0.9)));
+    world.add(Box::new(Csg::difference(
+        Box::new(Cube::new(
+            Point3::new(3.0, 0.0, -1.0),
+            Point3::new(5.0, 2.0, 1.0),
+            cube_material,
+        )),
+        Box::new(Cylinder::new(
+            Point3::new(4.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.6,
+            4.0,
+            drill_material,
+        )),
+    )));
+
+    // Sphère tronquée par une boîte
+    let sphere_material = Rc::new(Lambertian::new(Color::new(0.2, 0.4, 0.8)));
+    world.add(Box::new(Csg::intersection(
+        Box::new(Sphere::new(Point3::new(0.0, 1.2, 0.0), 1.2, sphere_material.clone())),
+        Box::new(Cube::new(
+            Point3::new(-1.0, 0.0, -1.0),
+            Point3::new(1.0, 2.0, 1.0),
+            sphere_material,
+        )),
+    )));
+
+    // Union d'une sphère et d'un cylindre
+    let union_material = Rc::new(Lambertian::new(Color::new(0.4, 0.7, 0.3)));
+    world.add(Box::new(Csg::union(
+        Box::new(Cylinder::new(
+            Point3::new(-4.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.5,
+            2.0,
+            union_material.clone(),
+        )),
+        Box::new(Sphere::new(Point3::new(-4.0, 2.2, 0.0), 0.9, union_material)),
+    )));
+
+    world
+}
+
+fn create_shapes_scene() -> HittableList {
+    let mut world = create_checker_ground_scene();
+
+    // Tore posé à plat
+    world.add(Box::new(Torus::new(
+        Point3::new(6.0, 0.4, 0.0),
+        1.0,
+        0.4,
+        Rc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.2)),
+    )));
+
+    // Disque au sol sous le tore
+    world.add(Box::new(Disk::new(
+        Point3::new(6.0, 0.001, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        1.8,
+        Rc::new(Lambertian::new(Color::new(0.9, 0.9, 0.9))),
+    )));
+
+    // Tronc de cône et cône pointu
+    world.add(Box::new(Cone::new(
+        Point3::new(3.0, 0.0, 0.0),
+        1.0,
+        0.5,
+        2.0,
+        Rc::new(Lambertian::new(Color::new(0.2, 0.5, 0.8))),
+    )));
+    world.add(Box::new(Cone::new(
+        Point3::new(3.0, 2.0, 0.0),
+        0.5,
+        0.0,
+        1.0,
+        Rc::new(Lambertian::new(Color::new(0.8, 0.2, 0.2))),
+    )));
+
+    // Anneau incliné
+    world.add(Box::new(Disk::annulus(
+        Point3::new(0.0, 1.5, 0.0),
+        Vec3::new(0.0, 0.3, -1.0),
+        1.3,
+        0.6,
+        Rc::new(Lambertian::new(Color::new(0.3, 0.7, 0.3))),
+    )));
+
+    // Capsule couchée en diagonale
+    world.add(Box::new(Capsule::new(
+        Point3::new(-3.5, 0.5, -1.0),
+        Point3::new(-2.5, 1.5, 1.0),
+        0.5,
+        Rc::new(Lambertian::new(Color::new(0.7, 0.3, 0.7))),
+    )));
+
+    // Ellipsoïde aplati
+    world.add(Box::new(Ellipsoid::new(
+        Point3::new(-6.0, 0.8, 0.0),
+        Vec3::new(1.2, 0.8, 0.6),
+        Rc::new(Metal::new(Color::new(0.7, 0.7, 0.7), 0.0)),
+    )));
+
+    world
+}
+
+fn create_sdf_scene() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Boîte aux arêtes arrondies rognée par une sphère, surmontée d'une bille
+    world.add(Box::new(SdfShape::new(
+        Sdf::round_box(Vec3::new(0.8, 0.8, 0.8), 0.2)
+            .intersection(Sdf::sphere(1.25))
+            .union(Sdf::sphere(0.3).translate(Vec3::new(0.0, 1.2, 0.0)))
+            .translate(Vec3::new(5.0, 1.0, 0.0)),
+        Rc::new(Lambertian::new(Color::new(0.8, 0.3, 0.3))),
+    )));
+
+    // Tore vrillé dressé à la verticale
+    world.add(Box::new(SdfShape::new(
+        Sdf::torus(0.8, 0.3).difference(Sdf::sphere(0.5)).twist(1.2).translate(Vec3::new(2.0, 1.1, 0.0)),
+        Rc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.1)),
+    )));
+
+    // Union douce de deux sphères
+    world.add(Box::new(SdfShape::new(
+        Sdf::sphere(0.8)
+            .translate(Vec3::new(-0.5, 0.8, 0.0))
+            .smooth_union(Sdf::sphere(0.6).translate(Vec3::new(0.5, 1.3, 0.0)), 0.4)
+            .translate(Vec3::new(-1.5, 0.0, 0.0)),
+        Rc::new(Lambertian::new(Color::new(0.2, 0.5, 0.8))),
+    )));
+
+    // Mandelbulb
+    world.add(Box::new(SdfShape::new(
+        Sdf::mandelbulb(8.0, 10).translate(Vec3::new(-5.0, 1.3, 0.0)),
+        Rc::new(Lambertian::new(Color::new(0.7, 0.7, 0.7))),
+    )));
+
+    world
+}
+
+fn create_motion_scene() -> HittableList {
+    let mut world = create_checker_ground_scene();
+
+    // Sphères en mouvement pendant l'ouverture de l'obturateur
+    world.add(Box::new(MovingSphere::new(
+        Point3::new(5.0, 1.0, 0.0),
+        Point3::new(5.0, 2.0, 0.0),
+        0.0,
+        1.0,
+        1.0,
+        Rc::new(Lambertian::new(Color::new(0.8, 0.3, 0.3))),
+    )));
+    world.add(Box::new(MovingSphere::new(
+        Point3::new(1.5, 0.7, -1.0),
+        Point3::new(2.5, 0.7, -1.0),
+        0.0,
+        1.0,
+        0.7,
+        Rc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.05)),
+    )));
+
+    // Cube qui glisse en tournant sur lui-même
+    world.add(Box::new(Transform::moving(
+        Box::new(Cube::new(
+            Point3::new(-0.75, -0.75, -0.75),
+            Point3::new(0.75, 0.75, 0.75),
+            Rc::new(Lambertian::new(Color::new(0.2, 0.5, 0.8))),
+        )),
+        Placement::new(Vec3::new(-1.5, 0.75, 0.0), Vec3::new(0.0, 0.0, 0.0), 1.0),
+        Placement::new(Vec3::new(-2.0, 0.75, 0.0), Vec3::new(0.0, 30.0, 0.0), 1.0),
+        0.0,
+        1.0,
+    )));
+
+    // Cylindre immobile couché par une instance
+    world.add(Box::new(Transform::new(
+        Box::new(Cylinder::new(
+            Point3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.5,
+            2.0,
+            Rc::new(Lambertian::new(Color::new(0.3, 0.7, 0.3))),
+        )),
+        Placement::new(Vec3::new(-5.0, 0.5, 0.0), Vec3::new(90.0, 0.0, 0.0), 1.0),
+    )));
+
+    world
+}
+
+fn create_bokeh_scene() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Sphère au point visé, nette
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Rc::new(Lambertian::new(Color::new(0.8, 0.3, 0.3))),
+    )));
+
+    // Petites lumières loin derrière et devant, floues avec une grande ouverture
+    let colors = [
+        Color::new(6.0, 4.0, 1.5),
+        Color::new(1.5, 3.0, 6.0),
+        Color::new(5.0, 1.5, 3.0),
+    ];
+    for i in 0..15 {
+        let x = -12.0 + 24.0 * (i as f64) / 14.0;
+        let behind = Point3::new(x, 0.8 + 1.5 * ((i % 4) as f64), 12.0 + 2.0 * ((i % 3) as f64));
+        world.add(Box::new(Sphere::new(
+            behind,
+            0.15,
+            Rc::new(Principled { emission: colors[i % 3], ..Principled::new(Color::new(0.0, 0.0, 0.0)) }),
+        )));
+    }
+    for i in 0..5 {
+        let x = 1.0 + 1.5 * (i as f64);
+        world.add(Box::new(Sphere::new(
+            Point3::new(x, 5.0 + 0.6 * (i as f64), -10.0),
+            0.05,
+            Rc::new(Principled { emission: colors[i % 3], ..Principled::new(Color::new(0.0, 0.0, 0.0)) }),
+        )));
+    }
+
+    world
+}
+
+// Scènes rendues par "all", dans l'ordre
+pub const SCENES: [&str; 16] = [
+    "cube", "cylinder", "sphere", "flat", "scene", "flat_and_cube", "bumpy", "metals", "glass", "principled", "fog",
+    "csg", "shapes", "sdf", "motion", "bokeh",
+];
+
+// Scène d'un objet, éclairée par la lumière ponctuelle commune. Les scènes
+// qui lisent des fichiers renvoient une erreur si elles ne les trouvent pas.
+pub fn create_scene(object_name: &str) -> Result<Scene> {
+    let world = match object_name {
+        "sphere" => create_sphere_scene(),
+        "cube" => create_cube_scene(),
+        "cylinder" => create_cylinder_scene(),
+        "flat" => create_plane_scene(),
+        "scene" => create_world_with_scene(),
+        "flat_and_cube" => create_flat_plane_and_cube(),
+        "bumpy" => create_bumpy_scene()?,
+        "metals" => create_metals_scene(),
+        "glass" => create_glass_scene(),
+        "principled" => create_principled_scene(),
+        "fog" => create_fog_scene()?,
+        "csg" => create_csg_scene(),
+        "shapes" => create_shapes_scene(),
+        "sdf" => create_sdf_scene(),
+        "motion" => create_motion_scene(),
+        "bokeh" => create_bokeh_scene(),
+        _ => return Err(Error::UnknownScene(object_name.to_string())),
+    };
+    let light = Light::new(Point3::new(-10.0, 10.0, -10.0), 0.2, Vec3::new(1.0, 1.0, 1.0));
+    Ok(Scene::new(world).with_light(light))
+}
+
+fn create_flat_plane_and_cube() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Ajout du cube
+    let cube_material = Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1));
+    let cube = Cube::new(
+        Point3::new(-1.0, 0.0, -1.0),
+        Point3::new(1.0, 2.0, 1.0),
+        cube_material,
+    );
+    world.add(Box::new(cube));
+
+
+    // Ajout de la surface plane
+    // let plane_material = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.8));
+    let plane_material = Rc::new(Dielectric::new(0.3));
+    let rect = Rectangle::new(
+        Point3::new(3.0, 0.1, -1.0),  
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -2.0), 
+        plane_material,
+    );
+    world.add(Box::new(rect));
+
+    world
+}
+
+fn create_world_with_scene() -> HittableList {
+    let mut world = create_ground_scene();
+
+    // Ajout du cube
+    let cube_material = Rc::new(Metal::new(Color::new(0.8, 0.3, 0.3), 0.1));
+    let cube = Cube::new(
+        Point3::new(-1.0, 0.0, -1.0),
+        Point3::new(1.0, 2.0, 1.0),
+        cube_material,
+    );
+    world.add(Box::new(cube));
+
+    // Ajout du cylindre
+    let cylinder_material = Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    let cylinder = Cylinder::new(
+        Point3::new(-4.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.8,
+        4.0,
+        cylinder_material,
+    );
+    world.add(Box::new(cylinder));
+
+
+
+    // Ajout de la sphère
+    let material3 = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+    world.add(Box::new(Sphere::new(
+        Point3::new(4.0, 1.0, 0.0),
+        1.0,
+        material3,
+    )));
+
+
+
+    // Ajout de la surface plane
+    // let plane_material = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.8));
+    let plane_material = Rc::new(Dielectric::new(0.3));
+    let rect = Rectangle::new(
+        Point3::new(6.0, 0.1, 0.0),  
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -2.0), 
+        plane_material,
+    );
+    world.add(Box::new(rect));
+
+    world
+}
@@ -0,0 +1,307 @@
+// Signed distance field shapes rendered by sphere tracing.
+//
+// A shape is described by a tree of `Sdf` nodes: primitives centered on the
+// origin, transforms and boolean combinations. `SdfShape` wraps a tree into a
+// Hittable by marching along the ray by the distance to the nearest surface.
+
+use std::rc::Rc;
+
+use crate::aabb::{self, Aabb};
+use crate::common;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+const MAX_STEPS: usize = 256;
+const SURFACE_EPSILON: f64 = 1.0e-4;
+
+pub enum Sdf {
+    Sphere { radius: f64 },
+    RoundBox { half_extents: Vec3, radius: f64 },
+    Torus { major_radius: f64, minor_radius: f64 },
+    Mandelbulb { power: f64, iterations: usize },
+    Translate { offset: Vec3, child: Box<Sdf> },
+    Twist { amount: f64, child: Box<Sdf> },
+    Union(Box<Sdf>, Box<Sdf>),
+    Intersection(Box<Sdf>, Box<Sdf>),
+    Difference(Box<Sdf>, Box<Sdf>),
+    SmoothUnion { k: f64, left: Box<Sdf>, right: Box<Sdf> },
+}
+
+impl Sdf {
+    pub fn sphere(radius: f64) -> Sdf {
+        Sdf::Sphere { radius }
+    }
+
+    // Box of the given half extents whose edges are rounded by `radius`
+    pub fn round_box(half_extents: Vec3, radius: f64) -> Sdf {
+        Sdf::RoundBox { half_extents, radius }
+    }
+
+    // Torus in the XZ plane
+    pub fn torus(major_radius: f64, minor_radius: f64) -> Sdf {
+        Sdf::Torus {
+            major_radius,
+            minor_radius,
+        }
+    }
+
+    // Mandelbulb fractal of the given power (8 is the classic one), about 1.2 units wide
+    pub fn mandelbulb(power: f64, iterations: usize) -> Sdf {
+        Sdf::Mandelbulb { power, iterations }
+    }
+
+    pub fn translate(self, offset: Vec3) -> Sdf {
+        Sdf::Translate {
+            offset,
+            child: Box::new(self),
+        }
+    }
+
+    // Rotate around the Y axis by `amount` radians per unit of height
+    pub fn twist(self, amount: f64) -> Sdf {
+        Sdf::Twist {
+            amount,
+            child: Box::new(self),
+        }
+    }
+
+    pub fn union(self, other: Sdf) -> Sdf {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn intersection(self, other: Sdf) -> Sdf {
+        Sdf::Intersection(Box::new(self), Box::new(other))
+    }
+
+    pub fn difference(self, other: Sdf) -> Sdf {
+        Sdf::Difference(Box::new(self), Box::new(other))
+    }
+
+    // Union blending the two shapes over a distance of about `k`
+    pub fn smooth_union(self, other: Sdf, k: f64) -> Sdf {
+        Sdf::SmoothUnion {
+            k,
+            left: Box::new(self),
+            right: Box::new(other),
+        }
+    }
+
+    pub fn distance(&self, p: Point3) -> f64 {
+        match self {
+            Sdf::Sphere { radius } => p.length() - radius,
+            Sdf::RoundBox { half_extents, radius } => {
+                let q = Vec3::new(
+                    p.x().abs() - half_extents.x(),
+                    p.y().abs() - half_extents.y(),
+                    p.z().abs() - half_extents.z(),
+                );
+                let outside = Vec3::new(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0)).length();
+                let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+                outside + inside - radius
+            }
+            Sdf::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let ring = f64::sqrt(p.x() * p.x() + p.z() * p.z()) - major_radius;
+                f64::sqrt(ring * ring + p.y() * p.y()) - minor_radius
+            }
+            Sdf::Mandelbulb { power, iterations } => mandelbulb_distance(p, *power, *iterations),
+            Sdf::Translate { offset, child } => child.distance(p - *offset),
+            Sdf::Twist { amount, child } => {
+                let angle = amount * p.y();
+                let (s, c) = f64::sin_cos(angle);
+                child.distance(Vec3::new(c * p.x() - s * p.z(), p.y(), s * p.x() + c * p.z()))
+            }
+            Sdf::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Sdf::Intersection(a, b) => a.distance(p).max(b.distance(p)),
+            Sdf::Difference(a, b) => a.distance(p).max(-b.distance(p)),
+            Sdf::SmoothUnion { k, left, right } => {
+                let d1 = left.distance(p);
+                let d2 = right.distance(p);
+                let h = common::clamp(0.5 + 0.5 * (d2 - d1) / k, 0.0, 1.0);
+                d2 + (d1 - d2) * h - k * h * (1.0 - h)
+            }
+        }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let cube = |half: f64| Aabb::new(Point3::new(-half, -half, -half), Point3::new(half, half, half));
+        match self {
+            Sdf::Sphere { radius } => cube(*radius),
+            Sdf::RoundBox { half_extents, radius } => Aabb::new(-*half_extents, *half_extents).padded(*radius),
+            Sdf::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let extent = major_radius + minor_radius;
+                Aabb::new(
+                    Point3::new(-extent, -minor_radius, -extent),
+                    Point3::new(extent, *minor_radius, extent),
+                )
+            }
+            Sdf::Mandelbulb { .. } => cube(1.25),
+            Sdf::Translate { offset, child } => {
+                let b = child.bounds();
+                Aabb::new(b.min() + *offset, b.max() + *offset)
+            }
+            Sdf::Twist { child, .. } => {
+                // Twisting sweeps the child around the Y axis
+                let b = child.bounds();
+                let radius = radial_extent(&b);
+                Aabb::new(
+                    Point3::new(-radius, b.min().y(), -radius),
+                    Point3::new(radius, b.max().y(), radius),
+                )
+            }
+            Sdf::Union(a, b) => aabb::surrounding_box(&a.bounds(), &b.bounds()),
+            Sdf::Intersection(a, b) => aabb::overlapping_box(&a.bounds(), &b.bounds()),
+            Sdf::Difference(a, _) => a.bounds(),
+            Sdf::SmoothUnion { k, left, right } => {
+                aabb::surrounding_box(&left.bounds(), &right.bounds()).padded(*k)
+            }
+        }
+    }
+
+    // Upper bound of the gradient length. Twisting stretches space, so the
+    // distance it returns overestimates the true one and steps must shrink.
+    fn lipschitz(&self) -> f64 {
+        match self {
+            Sdf::Translate { child, .. } => child.lipschitz(),
+            Sdf::Twist { amount, child } => {
+                child.lipschitz() * f64::sqrt(1.0 + (amount * radial_extent(&child.bounds())).powi(2))
+            }
+            Sdf::Union(a, b) | Sdf::Intersection(a, b) | Sdf::Difference(a, b) => {
+                a.lipschitz().max(b.lipschitz())
+            }
+            Sdf::SmoothUnion { left, right, .. } => left.lipschitz().max(right.lipschitz()),
+            _ => 1.0,
+        }
+    }
+}
+
+// Largest distance from the Y axis of any point of the box
+fn radial_extent(b: &Aabb) -> f64 {
+    let x = b.min().x().abs().max(b.max().x().abs());
+    let z = b.min().z().abs().max(b.max().z().abs());
+    f64::sqrt(x * x + z * z)
+}
+
+// Distance estimator of the Mandelbulb using the running derivative
+fn mandelbulb_distance(p: Point3, power: f64, iterations: usize) -> f64 {
+    let mut z = p;
+    let mut dr = 1.0;
+    let mut r = 0.0;
+    for _ in 0..iterations {
+        r = z.length();
+        if r > 2.0 {
+            break;
+        }
+
+        let theta = f64::acos(common::clamp(z.y() / r, -1.0, 1.0)) * power;
+        let phi = f64::atan2(z.z(), z.x()) * power;
+        dr = r.powf(power - 1.0) * power * dr + 1.0;
+
+        let zr = r.powf(power);
+        z = zr * Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin()) + p;
+    }
+    if r == 0.0 {
+        return 0.0;
+    }
+    0.5 * r.ln() * r / dr
+}
+
+pub struct SdfShape {
+    sdf: Sdf,
+    bbox: Aabb,
+    center: Point3,
+    step_scale: f64,
+    mat: Rc<dyn Material>,
+}
+
+impl SdfShape {
+    pub fn new(sdf: Sdf, mat: Rc<dyn Material>) -> SdfShape {
+        let bbox = sdf.bounds().padded(SURFACE_EPSILON);
+        let center = 0.5 * (bbox.min() + bbox.max());
+        let step_scale = 1.0 / sdf.lipschitz();
+        SdfShape {
+            sdf,
+            bbox,
+            center,
+            step_scale,
+            mat,
+        }
+    }
+
+    // Gradient of the field estimated with the tetrahedron technique
+    fn normal_at(&self, p: Point3) -> Vec3 {
+        let h = SURFACE_EPSILON;
+        let k = [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ];
+        let mut gradient = Vec3::default();
+        for offset in k {
+            gradient += offset * self.sdf.distance(p + h * offset);
+        }
+        if gradient.near_zero() {
+            return Vec3::new(0.0, 1.0, 0.0);
+        }
+        vec3::unit_vector(gradient)
+    }
+}
+
+impl Hittable for SdfShape {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Sdf);
+        // Only march over the part of the ray inside the bounds
+        let Some((t_enter, t_exit)) = self.bbox.interval(r) else {
+            return false;
+        };
+        let start = t_min.max(t_enter);
+        let end = t_max.min(t_exit);
+        if start >= end {
+            return false;
+        }
+
+        // Rays starting inside the shape (refraction) march towards the exit
+        let length = r.direction().length();
+        let mut t = start;
+        let side = self.sdf.distance(r.at(t)).signum();
+        for _ in 0..MAX_STEPS {
+            let d = side * self.sdf.distance(r.at(t));
+            if d < SURFACE_EPSILON {
+                if t <= t_min {
+                    t += 2.0 * SURFACE_EPSILON / length;
+                    continue;
+                }
+                rec.t = t;
+                rec.p = r.at(t);
+                rec.set_face_normal(r, self.normal_at(rec.p));
+
+                // Spherical projection around the center of the bounds
+                let dir = vec3::unit_vector(rec.p - self.center);
+                rec.u = (f64::atan2(-dir.z(), dir.x()) + common::PI) / (2.0 * common::PI);
+                rec.v = f64::acos(common::clamp(-dir.y(), -1.0, 1.0)) / common::PI;
+                rec.set_tangent_frame(Vec3::new(dir.z(), 0.0, -dir.x()));
+                rec.mat = Some(self.mat.clone());
+                return true;
+            }
+            t += d * self.step_scale / length;
+            if t >= end {
+                return false;
+            }
+        }
+        false
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        *output_box = self.bbox;
+        true
+    }
+}
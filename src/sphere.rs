@@ -1,9 +1,12 @@
 use std::rc::Rc;
  
-use crate::hittable::{HitRecord, Hittable};
+use crate::aabb::Aabb;
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec3::{self, Point3};
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
  
 pub struct Sphere {
     center: Point3,
@@ -19,10 +22,33 @@ impl Sphere {
             mat: m,
         }
     }
+
+    fn fill_record(&self, r: &Ray, t: f64, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(rec.t);
+        let outward_normal = (rec.p - self.center) / self.radius;
+        rec.set_face_normal(r, outward_normal);
+        let (u, v) = Self::get_sphere_uv(outward_normal);
+        rec.u = u;
+        rec.v = v;
+        // dp/du points along increasing longitude
+        rec.set_tangent_frame(Vec3::new(outward_normal.z(), 0.0, -outward_normal.x()));
+        rec.mat = Some(self.mat.clone());
+    }
+ 
+    fn get_sphere_uv(p: Point3) -> (f64, f64) {
+        // p: a given point on the sphere of radius one, centered at the origin
+        // u: returned value [0,1] of angle around the Y axis from X=-1
+        // v: returned value [0,1] of angle from Y=-1 to Y=+1
+        let theta = f64::acos(-p.y());
+        let phi = f64::atan2(-p.z(), p.x()) + common::PI;
+        (phi / (2.0 * common::PI), theta / common::PI)
+    }
 }
  
 impl Hittable for Sphere {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Sphere);
         let oc = r.origin() - self.center;
         let a = r.direction().length_squared();
         let half_b = vec3::dot(oc, r.direction());
@@ -43,11 +69,30 @@ impl Hittable for Sphere {
             }
         }
  
-        rec.t = root;
-        rec.p = r.at(rec.t);
-        let outward_normal = (rec.p - self.center) / self.radius;
-        rec.set_face_normal(r, outward_normal);
-        rec.mat = Some(self.mat.clone());
+        self.fill_record(r, root, rec);
+        true
+    }
+ 
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        *output_box = Aabb::new(self.center - r, self.center + r);
         true
     }
+ 
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        let oc = r.origin() - self.center;
+        let a = r.direction().length_squared();
+        let half_b = vec3::dot(oc, r.direction());
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return Vec::new();
+        }
+ 
+        let sqrt_d = f64::sqrt(discriminant);
+        let mut span = Span::default();
+        self.fill_record(r, (-half_b - sqrt_d) / a, &mut span.enter);
+        self.fill_record(r, (-half_b + sqrt_d) / a, &mut span.exit);
+        vec![span]
+    }
 }
\ No newline at end of file
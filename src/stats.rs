@@ -0,0 +1,181 @@
+use std::cell::Cell;
+use std::fmt;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::image;
+
+// Types of primitives whose intersection tests are counted
+#[derive(Clone, Copy)]
+pub enum Primitive {
+    Capsule,
+    Cone,
+    Csg,
+    Cube,
+    Cylinder,
+    Disk,
+    Ellipsoid,
+    Medium,
+    MovingSphere,
+    Plane,
+    Rectangle,
+    Sdf,
+    Sphere,
+    Torus,
+    Transform,
+}
+
+const PRIMITIVES: [Primitive; 15] = [
+    Primitive::Capsule,
+    Primitive::Cone,
+    Primitive::Csg,
+    Primitive::Cube,
+    Primitive::Cylinder,
+    Primitive::Disk,
+    Primitive::Ellipsoid,
+    Primitive::Medium,
+    Primitive::MovingSphere,
+    Primitive::Plane,
+    Primitive::Rectangle,
+    Primitive::Sdf,
+    Primitive::Sphere,
+    Primitive::Torus,
+    Primitive::Transform,
+];
+
+impl Primitive {
+    pub fn name(self) -> &'static str {
+        match self {
+            Primitive::Capsule => "capsule",
+            Primitive::Cone => "cone",
+            Primitive::Csg => "csg",
+            Primitive::Cube => "cube",
+            Primitive::Cylinder => "cylinder",
+            Primitive::Disk => "disk",
+            Primitive::Ellipsoid => "ellipsoid",
+            Primitive::Medium => "medium",
+            Primitive::MovingSphere => "moving_sphere",
+            Primitive::Plane => "plane",
+            Primitive::Rectangle => "rectangle",
+            Primitive::Sdf => "sdf",
+            Primitive::Sphere => "sphere",
+            Primitive::Torus => "torus",
+            Primitive::Transform => "transform",
+        }
+    }
+}
+
+// Intersection tests counted by the hit functions of the primitives, like the
+// random generator one counter set per thread
+thread_local! {
+    static INTERSECTION_TESTS: [Cell<u64>; PRIMITIVES.len()] = Default::default();
+}
+
+pub fn count_intersection_test(primitive: Primitive) {
+    INTERSECTION_TESTS.with(|tests| {
+        let count = &tests[primitive as usize];
+        count.set(count.get() + 1);
+    })
+}
+
+// Tests counted so far on this thread, in the order of PRIMITIVES
+fn intersection_tests() -> [u64; PRIMITIVES.len()] {
+    INTERSECTION_TESTS.with(|tests| tests.each_ref().map(Cell::get))
+}
+
+// Tests of all the primitives counted so far on this thread
+pub(crate) fn total_intersection_tests() -> u64 {
+    intersection_tests().iter().sum()
+}
+
+// What a render did and where the time went
+#[derive(Clone, Default)]
+pub struct RenderStats {
+    pub camera_rays: u64,
+    // Rays scattered by the materials
+    pub secondary_rays: u64,
+    pub shadow_rays: u64,
+    intersection_tests: [u64; PRIMITIVES.len()],
+    // Time of each phase of the render, in order
+    pub phases: Vec<(&'static str, Duration)>,
+}
+
+impl RenderStats {
+    // Start counting the intersection tests from now on
+    pub(crate) fn start(&mut self) {
+        self.intersection_tests = intersection_tests();
+    }
+
+    // Stop counting the intersection tests, keeping those since `start`
+    pub(crate) fn stop(&mut self) {
+        let start = self.intersection_tests;
+        for ((count, now), start) in self.intersection_tests.iter_mut().zip(intersection_tests()).zip(start) {
+            *count = now - start;
+        }
+    }
+
+    pub fn total_rays(&self) -> u64 {
+        self.camera_rays + self.secondary_rays + self.shadow_rays
+    }
+
+    // Average number of segments of the paths starting from the camera
+    pub fn average_path_length(&self) -> f64 {
+        if self.camera_rays == 0 {
+            return 0.0;
+        }
+        (self.camera_rays + self.secondary_rays) as f64 / self.camera_rays as f64
+    }
+
+    // Intersection tests of every type of primitive tested at least once
+    pub fn intersection_tests(&self) -> Vec<(&'static str, u64)> {
+        PRIMITIVES
+            .iter()
+            .zip(self.intersection_tests)
+            .filter(|(_, count)| *count > 0)
+            .map(|(primitive, count)| (primitive.name(), count))
+            .collect()
+    }
+
+    pub fn write_json(&self, file_name: &str) -> Result<()> {
+        let tests: Vec<String> = self
+            .intersection_tests()
+            .iter()
+            .map(|(name, count)| format!("\"{}\": {}", name, count))
+            .collect();
+        let phases: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(name, time)| format!("\"{}\": {}", name, time.as_secs_f64()))
+            .collect();
+
+        image::write_file(file_name, |writer| {
+            writeln!(writer, "{{")?;
+            writeln!(writer, "  \"camera_rays\": {},", self.camera_rays)?;
+            writeln!(writer, "  \"secondary_rays\": {},", self.secondary_rays)?;
+            writeln!(writer, "  \"shadow_rays\": {},", self.shadow_rays)?;
+            writeln!(writer, "  \"average_path_length\": {},", self.average_path_length())?;
+            writeln!(writer, "  \"intersection_tests\": {{{}}},", tests.join(", "))?;
+            writeln!(writer, "  \"phase_seconds\": {{{}}}", phases.join(", "))?;
+            writeln!(writer, "}}")
+        })
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Camera rays:         {:>14}", self.camera_rays)?;
+        writeln!(f, "Secondary rays:      {:>14}", self.secondary_rays)?;
+        writeln!(f, "Shadow rays:         {:>14}", self.shadow_rays)?;
+        writeln!(f, "Average path length: {:>14.3}", self.average_path_length())?;
+        writeln!(f, "Intersection tests:")?;
+        for (name, count) in self.intersection_tests() {
+            writeln!(f, "  {:<18} {:>14}", name, count)?;
+        }
+        write!(f, "Time:")?;
+        for (name, time) in &self.phases {
+            write!(f, "\n  {:<18} {:>12.3} s", name, time.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+// Helpers shared by the unit tests
+
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::material::{Lambertian, Material};
+use crate::vec3::Vec3;
+
+const EPSILON: f64 = 1.0e-6;
+
+pub fn material() -> Rc<dyn Material> {
+    Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+pub fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < EPSILON, "{} is not {}", actual, expected);
+}
+
+pub fn assert_vec_close(actual: Vec3, expected: Vec3) {
+    assert!((actual - expected).length() < EPSILON, "({}) is not ({})", actual, expected);
+}
+
+pub fn assert_box(output_box: &Aabb, min: Vec3, max: Vec3) {
+    assert_vec_close(output_box.min(), min);
+    assert_vec_close(output_box.max(), max);
+}
@@ -0,0 +1,155 @@
+use std::fs;
+
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::vec3::Point3;
+
+pub trait Texture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+}
+
+pub struct SolidColor {
+    color_value: Color,
+}
+
+impl SolidColor {
+    pub fn new(c: Color) -> SolidColor {
+        SolidColor { color_value: c }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.color_value
+    }
+}
+
+pub struct CheckerTexture {
+    scale: f64,
+    even: Color,
+    odd: Color,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Color, odd: Color) -> CheckerTexture {
+        CheckerTexture { scale, even, odd }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        let iu = f64::floor(u * self.scale) as i64;
+        let iv = f64::floor(v * self.scale) as i64;
+        if (iu + iv) % 2 == 0 {
+            self.even
+        } else {
+            self.odd
+        }
+    }
+}
+
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    data: Vec<Color>,
+}
+
+impl ImageTexture {
+    // Load a PPM image (ASCII P3 or binary P6). Texel values are returned in [0, 1]
+    // without gamma conversion, which is what normal and height maps expect.
+    pub fn load(path: &str) -> Result<ImageTexture> {
+        let (width, height, data) = read_ppm(path)?;
+        Ok(ImageTexture { width, height, data })
+    }
+}
+
+// Read an 8-bit PPM image (ASCII P3 or binary P6) as its width, height and
+// pixels in [0, 1], row by row from the top
+pub fn read_ppm(path: &str) -> Result<(usize, usize, Vec<Color>)> {
+    let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+    let invalid = |msg: &str| Error::invalid_file(path, msg);
+
+    let mut pos = 0;
+    let mut header = Vec::new();
+    while header.len() < 4 {
+        let token = next_token(&bytes, &mut pos).ok_or_else(|| invalid("truncated header"))?;
+        header.push(token);
+    }
+    let magic = header[0].clone();
+    let width: usize = header[1].parse().map_err(|_| invalid("bad width"))?;
+    let height: usize = header[2].parse().map_err(|_| invalid("bad height"))?;
+    let max_value: f64 = header[3].parse().map_err(|_| invalid("bad max value"))?;
+    if max_value <= 0.0 || max_value > 255.0 {
+        return Err(invalid("only 8-bit images are supported"));
+    }
+
+    let count = width.checked_mul(height).and_then(|n| n.checked_mul(3)).ok_or_else(|| invalid("image too large"))?;
+    let samples: Vec<f64> = match magic.as_str() {
+        "P3" => {
+            // Every value takes at least two bytes, so a bad size can't reserve more than the file
+            let mut samples = Vec::with_capacity(count.min(bytes.len() / 2));
+            for _ in 0..count {
+                let token = next_token(&bytes, &mut pos).ok_or_else(|| invalid("truncated pixel data"))?;
+                let value: f64 = token.parse().map_err(|_| invalid("bad pixel value"))?;
+                samples.push(value);
+            }
+            samples
+        }
+        "P6" => {
+            // A single whitespace byte separates the header from the raster
+            let start = pos + 1;
+            if bytes.len().saturating_sub(start) < count {
+                return Err(invalid("truncated pixel data"));
+            }
+            bytes[start..start + count].iter().map(|&b| b as f64).collect()
+        }
+        _ => return Err(invalid("not a P3/P6 image")),
+    };
+
+    let data = samples
+        .chunks(3)
+        .map(|c| Color::new(c[0] / max_value, c[1] / max_value, c[2] / max_value))
+        .collect();
+    Ok((width, height, data))
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        if self.data.is_empty() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        // Wrap the coordinates so that textures tile, and flip v to image rows
+        let u = u - f64::floor(u);
+        let v = 1.0 - (v - f64::floor(v));
+
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.data[j * self.width + i]
+    }
+}
+
+// Read the next whitespace separated token of a PPM file, skipping comments
+fn next_token(bytes: &[u8], pos: &mut usize) -> Option<String> {
+    loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if *pos < bytes.len() && bytes[*pos] == b'#' {
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+            continue;
+        }
+        break;
+    }
+
+    let start = *pos;
+    while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+    if start == *pos {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
+}
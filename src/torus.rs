@@ -0,0 +1,216 @@
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::material::Material;
+use crate::poly;
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+// Torus lying in the XZ plane around `center`, revolving a circle of radius
+// `minor_radius` at distance `major_radius` from the Y axis
+pub struct Torus {
+    center: Point3,
+    major_radius: f64,
+    minor_radius: f64,
+    mat: Rc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(center: Point3, major_radius: f64, minor_radius: f64, mat: Rc<dyn Material>) -> Torus {
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+            mat,
+        }
+    }
+
+    // Sorted roots of (|p|^2 + R^2 - r^2)^2 - 4R^2(x^2 + z^2) = 0 along the ray line
+    fn roots(&self, r: &Ray) -> Vec<f64> {
+        // Work with a unit direction to keep the quartic well conditioned
+        let length = r.direction().length();
+        let d = r.direction() / length;
+        let o = r.origin() - self.center;
+
+        let rr = self.major_radius * self.major_radius;
+        let f = vec3::dot(o, d);
+        let g = o.length_squared();
+        let k = g + rr - self.minor_radius * self.minor_radius;
+
+        let c4 = 1.0;
+        let c3 = 4.0 * f;
+        let c2 = 4.0 * f * f + 2.0 * k - 4.0 * rr * (1.0 - d.y() * d.y());
+        let c1 = 4.0 * f * k - 8.0 * rr * (f - o.y() * d.y());
+        let c0 = k * k - 4.0 * rr * (g - o.y() * o.y());
+
+        let mut roots: Vec<f64> = poly::solve_quartic([c0, c1, c2, c3, c4])
+            .into_iter()
+            .map(|s| s / length)
+            .collect();
+        roots.sort_by(|a, b| a.total_cmp(b));
+        roots.dedup_by(|a, b| (*a - *b).abs() < 1.0e-9);
+        roots
+    }
+
+    // Whether a point lies inside the tube, where the implicit function is negative
+    fn contains(&self, p: Point3) -> bool {
+        let local = p - self.center;
+        let rr = self.major_radius * self.major_radius;
+        let k = local.length_squared() + rr - self.minor_radius * self.minor_radius;
+        k * k - 4.0 * rr * (local.x() * local.x() + local.z() * local.z()) < 0.0
+    }
+
+    fn fill_record(&self, r: &Ray, t: f64, rec: &mut HitRecord) {
+        rec.t = t;
+        rec.p = r.at(t);
+        let local = rec.p - self.center;
+
+        // The normal points away from the closest point of the central ring
+        let radial = Vec3::new(local.x(), 0.0, local.z());
+        let ring = if radial.near_zero() {
+            Vec3::new(self.major_radius, 0.0, 0.0)
+        } else {
+            self.major_radius * vec3::unit_vector(radial)
+        };
+        let outward_normal = vec3::unit_vector(local - ring);
+        rec.set_face_normal(r, outward_normal);
+
+        // u goes around the Y axis, v around the tube
+        let phi = f64::atan2(local.z(), local.x());
+        let theta = f64::atan2(local.y(), radial.length() - self.major_radius);
+        rec.u = (phi + common::PI) / (2.0 * common::PI);
+        rec.v = (theta + common::PI) / (2.0 * common::PI);
+        rec.set_tangent_frame(Vec3::new(-local.z(), 0.0, local.x()));
+        rec.mat = Some(self.mat.clone());
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Torus);
+        let mut bbox = Aabb::default();
+        self.bounding_box(&mut bbox);
+        if !bbox.hit(r, t_min, t_max) {
+            return false;
+        }
+
+        match self.roots(r).into_iter().find(|&t| t > t_min && t < t_max) {
+            Some(t) => {
+                self.fill_record(r, t, rec);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let extent = self.major_radius + self.minor_radius;
+        let half = Vec3::new(extent, self.minor_radius, extent);
+        *output_box = Aabb::new(self.center - half, self.center + half);
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        // A tangent root does not switch between inside and outside, so the
+        // implicit function is sampled between consecutive roots instead of
+        // pairing them up
+        let roots = self.roots(r);
+        let mut spans: Vec<Span> = Vec::new();
+        for pair in roots.windows(2) {
+            if !self.contains(r.at(0.5 * (pair[0] + pair[1]))) {
+                continue;
+            }
+            match spans.last_mut() {
+                // Touching the surface from inside continues the same span
+                Some(span) if span.exit.t == pair[0] => self.fill_record(r, pair[1], &mut span.exit),
+                _ => {
+                    let mut span = Span::default();
+                    self.fill_record(r, pair[0], &mut span.enter);
+                    self.fill_record(r, pair[1], &mut span.exit);
+                    spans.push(span);
+                }
+            }
+        }
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_box, assert_close, assert_vec_close, material};
+
+    fn torus() -> Torus {
+        Torus::new(Point3::new(0.0, 0.0, 0.0), 2.0, 0.5, material())
+    }
+
+    #[test]
+    fn hit_outer_then_inner_side_of_the_tube() {
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(torus().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 2.5);
+        assert_vec_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(rec.front_face);
+
+        // Leaving the tube towards the hole
+        assert!(torus().hit(&r, 3.0, common::INFINITY, &mut rec));
+        assert_close(rec.t, 3.5);
+        assert_vec_close(rec.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn hit_with_unnormalized_direction() {
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0), 0.0);
+        let mut rec = HitRecord::new();
+        assert!(torus().hit(&r, 0.001, common::INFINITY, &mut rec));
+        assert_close(rec.t, 1.25);
+    }
+
+    #[test]
+    fn miss_through_the_hole() {
+        let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!(!torus().hit(&r, 0.001, common::INFINITY, &mut HitRecord::new()));
+        assert!(torus().hit_intervals(&r).is_empty());
+    }
+
+    #[test]
+    fn two_spans_across_the_ring() {
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let spans = torus().hit_intervals(&r);
+        assert_eq!(spans.len(), 2);
+        for (span, (enter, exit)) in spans.iter().zip([(2.5, 3.5), (6.5, 7.5)]) {
+            assert_close(span.enter.t, enter);
+            assert_close(span.exit.t, exit);
+        }
+    }
+
+    #[test]
+    fn tangent_ray_over_the_tube_has_no_span() {
+        let r = Ray::new(Point3::new(-5.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert!(torus().hit_intervals(&r).is_empty());
+    }
+
+    #[test]
+    fn tangent_ray_inside_the_tube_keeps_one_span() {
+        // Grazes the inner equator at z = 0 without leaving the tube
+        let r = Ray::new(Point3::new(1.5, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let spans = torus().hit_intervals(&r);
+        assert_eq!(spans.len(), 1);
+        assert_close(spans[0].enter.t, 3.0);
+        assert_close(spans[0].exit.t, 7.0);
+        assert!(spans[0].enter.front_face);
+        assert!(!spans[0].exit.front_face);
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut output_box = Aabb::default();
+        assert!(torus().bounding_box(&mut output_box));
+        assert_box(&output_box, Vec3::new(-2.5, -0.5, -2.5), Vec3::new(2.5, 0.5, 2.5));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::aabb::{self, Aabb};
+use crate::common;
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::ray::Ray;
+use crate::stats::{self, Primitive};
+use crate::vec3::{self, Point3, Vec3};
+
+// Position, orientation (Euler angles in degrees, applied X then Y then Z) and
+// uniform scale of an instance
+#[derive(Clone, Copy)]
+pub struct Placement {
+    pub translation: Vec3,
+    pub rotation: Vec3,
+    pub scale: f64,
+}
+
+impl Placement {
+    pub fn new(translation: Vec3, rotation: Vec3, scale: f64) -> Placement {
+        Placement {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    fn lerp(&self, other: &Placement, s: f64) -> Placement {
+        Placement {
+            translation: self.translation + s * (other.translation - self.translation),
+            rotation: self.rotation + s * (other.rotation - self.rotation),
+            scale: self.scale + s * (other.scale - self.scale),
+        }
+    }
+
+    // Rows of the rotation matrix Rz * Ry * Rx
+    fn rotation_rows(&self) -> [Vec3; 3] {
+        let (sx, cx) = f64::sin_cos(common::degrees_to_radians(self.rotation.x()));
+        let (sy, cy) = f64::sin_cos(common::degrees_to_radians(self.rotation.y()));
+        let (sz, cz) = f64::sin_cos(common::degrees_to_radians(self.rotation.z()));
+        [
+            Vec3::new(cz * cy, cz * sy * sx - sz * cx, cz * sy * cx + sz * sx),
+            Vec3::new(sz * cy, sz * sy * sx + cz * cx, sz * sy * cx - cz * sx),
+            Vec3::new(-sy, cy * sx, cy * cx),
+        ]
+    }
+}
+
+// Rotation matrix with its rows, applied forward or transposed (inverse)
+struct Rotation {
+    rows: [Vec3; 3],
+}
+
+impl Rotation {
+    fn apply(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            vec3::dot(self.rows[0], v),
+            vec3::dot(self.rows[1], v),
+            vec3::dot(self.rows[2], v),
+        )
+    }
+
+    fn apply_inverse(&self, v: Vec3) -> Vec3 {
+        v.x() * self.rows[0] + v.y() * self.rows[1] + v.z() * self.rows[2]
+    }
+}
+
+// Instance of an object placed in the world. The placement may change over
+// the shutter interval: it is interpolated linearly at the time of each ray,
+// so animated instances are motion blurred.
+pub struct Transform {
+    object: Box<dyn Hittable>,
+    start: Placement,
+    end: Placement,
+    time0: f64,
+    time1: f64,
+}
+
+impl Transform {
+    pub fn new(object: Box<dyn Hittable>, placement: Placement) -> Transform {
+        Transform::moving(object, placement, placement, 0.0, 1.0)
+    }
+
+    // Instance going from `start` at `time0` to `end` at `time1`
+    pub fn moving(
+        object: Box<dyn Hittable>,
+        start: Placement,
+        end: Placement,
+        time0: f64,
+        time1: f64,
+    ) -> Transform {
+        Transform {
+            object,
+            start,
+            end,
+            time0,
+            time1,
+        }
+    }
+
+    fn placement_at(&self, time: f64) -> Placement {
+        if self.time1 <= self.time0 {
+            return self.start;
+        }
+        let s = common::clamp((time - self.time0) / (self.time1 - self.time0), 0.0, 1.0);
+        self.start.lerp(&self.end, s)
+    }
+
+    // The ray expressed in object space. Parameters along the ray are the same
+    // in both spaces since the direction is transformed with the origin.
+    fn to_object(r: &Ray, placement: &Placement, rotation: &Rotation) -> Ray {
+        Ray::new(
+            rotation.apply_inverse(r.origin() - placement.translation) / placement.scale,
+            rotation.apply_inverse(r.direction()) / placement.scale,
+            r.time(),
+        )
+    }
+
+    fn to_world(rec: &mut HitRecord, placement: &Placement, rotation: &Rotation) {
+        // Rotations and uniform scales keep normals perpendicular and the face side
+        rec.p = rotation.apply(placement.scale * rec.p) + placement.translation;
+        rec.normal = rotation.apply(rec.normal);
+        rec.tangent = rotation.apply(rec.tangent);
+        rec.bitangent = rotation.apply(rec.bitangent);
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        stats::count_intersection_test(Primitive::Transform);
+        let placement = self.placement_at(r.time());
+        let rotation = Rotation {
+            rows: placement.rotation_rows(),
+        };
+        if !self.object.hit(&Self::to_object(r, &placement, &rotation), t_min, t_max, rec) {
+            return false;
+        }
+        Self::to_world(rec, &placement, &rotation);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut Aabb) -> bool {
+        let mut object_box = Aabb::default();
+        if !self.object.bounding_box(&mut object_box) {
+            return false;
+        }
+
+        let min = object_box.min();
+        let max = object_box.max();
+        let corners: Vec<Point3> = (0..8)
+            .map(|i| {
+                Point3::new(
+                    if i & 1 == 0 { min.x() } else { max.x() },
+                    if i & 2 == 0 { min.y() } else { max.y() },
+                    if i & 4 == 0 { min.z() } else { max.z() },
+                )
+            })
+            .collect();
+
+        let placed_box = |placement: &Placement| {
+            if self.start.rotation == self.end.rotation {
+                // Without a change of orientation every point moves linearly,
+                // so the boxes at both ends of the motion bound the whole sweep
+                let rotation = Rotation {
+                    rows: placement.rotation_rows(),
+                };
+                let mut placed = corners
+                    .iter()
+                    .map(|&c| rotation.apply(placement.scale * c) + placement.translation);
+                let first = placed.next().unwrap();
+                placed.fold(Aabb::new(first, first), |b, p| {
+                    aabb::surrounding_box(&b, &Aabb::new(p, p))
+                })
+            } else {
+                // A spinning object stays within the ball holding it in any orientation
+                let radius = placement.scale
+                    * corners.iter().map(|c| c.length()).fold(0.0, f64::max);
+                let r = Vec3::new(radius, radius, radius);
+                Aabb::new(placement.translation - r, placement.translation + r)
+            }
+        };
+        *output_box = aabb::surrounding_box(&placed_box(&self.start), &placed_box(&self.end));
+        true
+    }
+
+    fn hit_intervals(&self, r: &Ray) -> Vec<Span> {
+        let placement = self.placement_at(r.time());
+        let rotation = Rotation {
+            rows: placement.rotation_rows(),
+        };
+        let mut spans = self.object.hit_intervals(&Self::to_object(r, &placement, &rotation));
+        for span in &mut spans {
+            Self::to_world(&mut span.enter, &placement, &rotation);
+            Self::to_world(&mut span.exit, &placement, &rotation);
+        }
+        spans
+    }
+}
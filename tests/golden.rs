@@ -0,0 +1,131 @@
+// Golden image regression tests: every built-in scene is rendered small, with a
+// fixed random seed, and compared with its reference image in tests/golden.
+//
+// A render is accepted if the RMSE of its 8-bit values (scaled to [0, 1]) to
+// the reference is within TOLERANCE. The same code renders the same image, the
+// tolerance leaves room for floating point differences between platforms
+// which send a few paths elsewhere, while staying below the noise between two
+// seeds (an RMSE of 0.007 to 0.03). On failure the render and a difference
+// image are written to the target directory for a look.
+//
+// After an intended change of the images, regenerate the references with
+//     UPDATE_GOLDEN=1 cargo test --test golden
+// and check them before committing.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rt::camera::{PerspectiveCamera, View};
+use rt::common;
+use rt::scenes::{self, SCENES};
+use rt::texture;
+use rt::vec3::{Point3, Vec3};
+use rt::Renderer;
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 36;
+const SAMPLES_PER_PIXEL: u32 = 16;
+const SEED: u64 = 2024;
+const TOLERANCE: f64 = 0.005;
+
+fn reference_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.ppm", name))
+}
+
+fn output_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden").join(name)
+}
+
+// Binary PPM of 8-bit RGB values, row by row from the top
+fn write_ppm(path: &Path, width: usize, height: usize, rgb: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut file = fs::File::create(path).unwrap();
+    write!(file, "P6\n{} {}\n255\n", width, height).unwrap();
+    file.write_all(rgb).unwrap();
+}
+
+// The scene seen by the default camera of the command line
+fn render(name: &str) -> Vec<u8> {
+    let scene = scenes::create_scene(name).unwrap();
+    let lookfrom = Point3::new(6.0, 12.0, -20.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let view = View::new(lookfrom, lookat, Vec3::new(0.0, 1.0, 0.0)).with_shutter(0.0, 1.0);
+    let aspect_ratio = WIDTH as f64 / HEIGHT as f64;
+    let camera = PerspectiveCamera::new(view, 40.0, aspect_ratio, 0.0, (lookat - lookfrom).length());
+
+    common::seed_rng(SEED);
+    let image = Renderer::new(WIDTH, HEIGHT)
+        .with_samples(SAMPLES_PER_PIXEL)
+        .render(&scene, &camera)
+        .unwrap();
+    image.to_rgb8()
+}
+
+fn check_scene(name: &str) {
+    let actual = render(name);
+    let reference = reference_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        write_ppm(&reference, WIDTH, HEIGHT, &actual);
+        return;
+    }
+
+    let (width, height, expected) = texture::read_ppm(reference.to_str().unwrap())
+        .unwrap_or_else(|e| panic!("{} (regenerate the references with UPDATE_GOLDEN=1)", e));
+    assert_eq!((width, height), (WIDTH, HEIGHT), "size of {}", reference.display());
+
+    let expected: Vec<f64> = expected.iter().flat_map(|c| [c.x(), c.y(), c.z()]).collect();
+    let squared_error: f64 = actual
+        .iter()
+        .zip(&expected)
+        .map(|(&a, &e)| (a as f64 / 255.0 - e).powi(2))
+        .sum();
+    let rmse = (squared_error / expected.len() as f64).sqrt();
+    if rmse <= TOLERANCE {
+        return;
+    }
+
+    // Absolute difference, amplified 4 times to see small ones
+    let diff: Vec<u8> = actual
+        .iter()
+        .zip(&expected)
+        .map(|(&a, &e)| ((a as f64 - 255.0 * e).abs() * 4.0).min(255.0) as u8)
+        .collect();
+    let actual_path = output_path(&format!("{}_actual.ppm", name));
+    let diff_path = output_path(&format!("{}_diff.ppm", name));
+    write_ppm(&actual_path, WIDTH, HEIGHT, &actual);
+    write_ppm(&diff_path, WIDTH, HEIGHT, &diff);
+    panic!(
+        "{}: RMSE {:.4} to the reference is above {} (render: {}, difference: {})",
+        name,
+        rmse,
+        TOLERANCE,
+        actual_path.display(),
+        diff_path.display()
+    );
+}
+
+macro_rules! golden_tests {
+    ($($name:ident),*) => {
+        $(
+            #[test]
+            fn $name() {
+                check_scene(stringify!($name));
+            }
+        )*
+
+        #[test]
+        fn every_scene_is_tested() {
+            let tested = [$(stringify!($name)),*];
+            for name in SCENES {
+                assert!(tested.contains(&name), "no golden test for scene '{}'", name);
+            }
+        }
+    };
+}
+
+golden_tests!(
+    cube, cylinder, sphere, flat, scene, flat_and_cube, bumpy, metals, glass, principled, fog, csg, shapes, sdf,
+    motion, bokeh
+);